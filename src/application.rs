@@ -27,6 +27,11 @@ mod imp {
         pub window: OnceCell<WeakRef<PacketApplicationWindow>>,
 
         pub start_in_background: Cell<bool>,
+        pub is_quitting: Cell<bool>,
+
+        #[default(gio::Settings::new(APP_ID))]
+        pub settings: gio::Settings,
+        pub accent_css_provider: gtk::CssProvider,
 
         #[default(async_channel::bounded(1))]
         pub send_files_channel: AsyncChannel<Vec<String>>,
@@ -73,6 +78,33 @@ mod imp {
             }
         }
 
+        fn open(&self, files: &[gio::File], hint: &str) {
+            debug!(
+                files = files.len(),
+                hint, "GtkApplication<PacketApplication>::open"
+            );
+            // Opening files is how file manager integrations without D-Bus
+            // access, such as Dolphin's service menu, queue files for sending
+            self.obj().activate();
+
+            let files = files
+                .iter()
+                .filter_map(|it| it.path())
+                .map(|it| it.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            if files.is_empty() {
+                return;
+            }
+
+            let tx = self.send_files_channel.0.clone();
+            glib::spawn_future_local(async move {
+                _ = tx
+                    .send(files)
+                    .await
+                    .inspect_err(|err| tracing::warn!("{err:#}"));
+            });
+        }
+
         fn startup(&self) {
             debug!("GtkApplication<PacketApplication>::startup");
             self.parent_startup();
@@ -84,6 +116,8 @@ mod imp {
             app.setup_css();
             app.setup_gactions();
             app.setup_accels();
+            #[cfg(target_os = "linux")]
+            app.setup_signal_handlers();
         }
 
         fn dbus_register(
@@ -187,10 +221,7 @@ impl PacketApplication {
                 tracing::debug!("Invoked action app.quit");
 
                 // On GNOME, closing the background app from their "Background Apps" UI seems to invoke app.quit
-                app.main_window().imp().should_quit.replace(true);
-
-                app.main_window().close();
-                app.quit();
+                app.quit_gracefully();
             })
             .build();
 
@@ -203,6 +234,47 @@ impl PacketApplication {
         self.add_action_entries([action_quit, action_about]);
     }
 
+    /// Closes the window regardless of the close action, so that its
+    /// `close_request` gets to stop the RQS service and remove lingering
+    /// notifications before the app quits.
+    ///
+    /// The app quits by itself once the window is gone.
+    fn quit_gracefully(&self) {
+        if self.imp().is_quitting.replace(true) {
+            return;
+        }
+
+        match self.imp().window.get().and_then(|it| it.upgrade()) {
+            Some(window) => window.quit_window(),
+            None => self.quit(),
+        }
+    }
+
+    /// Without these, the session ending or a `kill` would skip the teardown in
+    /// the window's `close_request`.
+    #[cfg(target_os = "linux")]
+    fn setup_signal_handlers(&self) {
+        const SIGINT: i32 = 2;
+        const SIGTERM: i32 = 15;
+
+        for signum in [SIGINT, SIGTERM] {
+            glib::unix_signal_add_local(
+                signum,
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        info!(signum, "Received signal, quitting");
+                        this.quit_gracefully();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+        }
+    }
+
     // Sets up keyboard shortcuts
     fn setup_accels(&self) {
         // This will close the app regardless of "Run in Background"
@@ -223,6 +295,56 @@ impl PacketApplication {
                 gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
             );
         }
+
+        self.setup_accent_color();
+    }
+
+    /// `adw::StyleManager` only reports the system accent color, so the
+    /// override goes on top of it through CSS instead.
+    fn setup_accent_color(&self) {
+        let imp = self.imp();
+
+        if let Some(display) = gdk::Display::default() {
+            gtk::style_context_add_provider_for_display(
+                &display,
+                &imp.accent_css_provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+            );
+        }
+
+        self.apply_accent_color();
+        imp.settings.connect_changed(
+            Some("accent-color"),
+            clone!(
+                #[weak(rename_to = app)]
+                self,
+                move |_, _| app.apply_accent_color()
+            ),
+        );
+    }
+
+    fn apply_accent_color(&self) {
+        let imp = self.imp();
+
+        let accent_color = match imp.settings.string("accent-color").as_str() {
+            "blue" => Some(adw::AccentColor::Blue),
+            "teal" => Some(adw::AccentColor::Teal),
+            "green" => Some(adw::AccentColor::Green),
+            "yellow" => Some(adw::AccentColor::Yellow),
+            "orange" => Some(adw::AccentColor::Orange),
+            "red" => Some(adw::AccentColor::Red),
+            "pink" => Some(adw::AccentColor::Pink),
+            "purple" => Some(adw::AccentColor::Purple),
+            "slate" => Some(adw::AccentColor::Slate),
+            _ => None,
+        };
+        debug!(?accent_color, "Applying accent color");
+
+        // The standalone accent color is derived from the background one
+        let css = accent_color
+            .map(|it| format!(":root {{ --accent-bg-color: {}; }}", it.to_rgba()))
+            .unwrap_or_default();
+        imp.accent_css_provider.load_from_string(&css);
     }
 
     #[allow(dead_code)]
@@ -331,6 +453,7 @@ impl Default for PacketApplication {
     fn default() -> Self {
         glib::Object::builder()
             .property("application-id", APP_ID)
+            .property("flags", gio::ApplicationFlags::HANDLES_OPEN)
             .property("resource-base-path", "/io/github/nozwock/Packet/")
             .build()
     }
@@ -4,6 +4,11 @@ use std::{path::PathBuf, sync::OnceLock};
 #[allow(dead_code)]
 pub const XDP_XATTR_HOST_PATH: &str = "xattr::document-portal.host-path";
 
+/// Size past which the log file gets rotated.
+pub const LOG_FILE_MAX_SIZE: u64 = 5 * 1024 * 1024;
+/// Number of rotated log files to keep around, besides the current one.
+pub const LOG_FILE_MAX_ROTATED: usize = 2;
+
 pub fn packet_log_path() -> &'static PathBuf {
     static PACKET_LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
     PACKET_LOG_PATH.get_or_init(|| dirs::cache_dir().unwrap_or_default().join("packet.log"))
@@ -6,7 +6,6 @@ mod ext;
 mod monitors;
 mod objects;
 mod plugins;
-#[cfg(target_os = "linux")]
 mod tray;
 mod utils;
 mod widgets;
@@ -19,7 +18,8 @@ use tracing_subscriber::EnvFilter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::constants::packet_log_path;
+use crate::constants::{LOG_FILE_MAX_ROTATED, LOG_FILE_MAX_SIZE, packet_log_path};
+use crate::utils::RotatingLogFile;
 
 use self::application::PacketApplication;
 use self::config::{GETTEXT_PACKAGE, LOCALEDIR, RESOURCES_FILE};
@@ -38,17 +38,27 @@ fn main() -> glib::ExitCode {
 
     let stdout_layer = tracing_subscriber::fmt::layer().with_line_number(true);
     let (file_writer, _file_guard) = tracing_appender::non_blocking(
-        fs_err::File::create(packet_log_path()).expect("Couldn't create the log file"),
+        RotatingLogFile::create(packet_log_path(), LOG_FILE_MAX_SIZE, LOG_FILE_MAX_ROTATED)
+            .expect("Couldn't create the log file"),
     );
     let file_layer = tracing_subscriber::fmt::layer()
         .with_writer(file_writer)
         .with_line_number(true)
         .with_ansi(false);
+    // Machine-readable logs for bug reports, only for the file since the
+    // stdout is meant for humans
+    let (file_layer, json_file_layer) =
+        if std::env::var_os("PACKET_LOG_JSON").is_some_and(|it| it == "1") {
+            (None, Some(file_layer.json()))
+        } else {
+            (Some(file_layer), None)
+        };
 
     // Initialize logger
     tracing_subscriber::registry()
         .with(stdout_layer)
         .with(file_layer)
+        .with(json_file_layer)
         .with(env_filter)
         .init();
 
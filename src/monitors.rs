@@ -1,21 +1,154 @@
+use std::time::Duration;
+
 use futures_lite::StreamExt;
-use tokio::sync::watch;
+use gtk::gio::{self, prelude::*};
+use tokio::sync::{broadcast, watch};
+
+use crate::utils;
+
+const BLUETOOTH_MONITOR_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Where the reconnect delay stops doubling, for when there's no BlueZ or
+/// adapter at all.
+const BLUETOOTH_MONITOR_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// A finer picture of the network than whether it's available or not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NetworkState {
+    /// Not a single network interface is up, e.g. in airplane mode
+    #[default]
+    NoInterface,
+    /// There's an interface up, but it's not connected to a network
+    Disconnected,
+    /// Connected to a network that doesn't reach the internet, which doesn't
+    /// matter for reaching nearby devices
+    Limited,
+    Full,
+}
+
+impl NetworkState {
+    pub fn from_monitor(monitor: &gio::NetworkMonitor) -> Self {
+        match monitor.connectivity() {
+            gio::NetworkConnectivity::Local if !utils::has_active_network_interface() => {
+                Self::NoInterface
+            }
+            gio::NetworkConnectivity::Local => Self::Disconnected,
+            gio::NetworkConnectivity::Full => Self::Full,
+            _ => Self::Limited,
+        }
+    }
 
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Limited | Self::Full)
+    }
+}
+
+/// Everything that needs to be up for devices to find and reach each other,
+/// broadcast as a whole whenever any part of it changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectivityState {
+    pub network: NetworkState,
+    pub bluetooth: bool,
+}
+
+/// Resolves once the network goes away, or never if the sender is dropped.
+pub async fn wait_for_network_loss(mut rx: broadcast::Receiver<ConnectivityState>) {
+    loop {
+        match rx.recv().await {
+            Ok(state) if !state.network.is_available() => return,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// Keeps the powered state up to date, reconnecting to BlueZ whenever the
+/// connection to it is lost, e.g. when it's restarted.
+///
+/// Retries back off up to [`BLUETOOTH_MONITOR_MAX_RECONNECT_DELAY`], and only
+/// the first failure after having been connected is warned about, so that
+/// systems without Bluetooth don't fill up the log.
+///
+/// Only returns once all of the receivers are dropped.
 pub async fn spawn_bluetooth_power_monitor_task(
     conn: zbus::Connection,
     sender: watch::Sender<bool>,
+) -> zbus::Result<()> {
+    let mut reconnect_delay = BLUETOOTH_MONITOR_RECONNECT_DELAY;
+    loop {
+        let mut is_connected = false;
+        let result = monitor_bluetooth_power(&conn, &sender, &mut is_connected).await;
+        if is_connected {
+            reconnect_delay = BLUETOOTH_MONITOR_RECONNECT_DELAY;
+        }
+        match result {
+            Err(err) if is_connected => {
+                tracing::warn!(%err, "Lost the Bluetooth powered state monitor");
+            }
+            Err(err) => {
+                tracing::debug!(%err, "Couldn't connect the Bluetooth powered state monitor");
+            }
+            Ok(()) => {}
+        }
+
+        if sender.is_closed() {
+            return Ok(());
+        }
+
+        futures_timer::Delay::new(reconnect_delay).await;
+        reconnect_delay = (reconnect_delay * 2).min(BLUETOOTH_MONITOR_MAX_RECONNECT_DELAY);
+        tracing::debug!("Reconnecting the Bluetooth powered state monitor");
+    }
+}
+
+/// Sets `is_connected` once the adapter's state could be read.
+async fn monitor_bluetooth_power(
+    conn: &zbus::Connection,
+    sender: &watch::Sender<bool>,
+    is_connected: &mut bool,
 ) -> zbus::Result<()> {
     let proxy =
-        zbus::Proxy::new(&conn, "org.bluez", "/org/bluez/hci0", "org.bluez.Adapter1").await?;
+        zbus::Proxy::new(conn, "org.bluez", "/org/bluez/hci0", "org.bluez.Adapter1").await?;
+
+    // The state might've changed while we weren't listening
+    let powered: bool = proxy.get_property("Powered").await?;
+    send_bluetooth_state(sender, powered);
+    *is_connected = true;
 
     let mut property_stream = proxy.receive_property_changed::<bool>("Powered").await;
-    while let Some(event) = property_stream.next().await {
-        if let Ok(powered) = event.get().await {
-            _ = sender.send(powered);
+    let mut owner_stream = proxy.receive_owner_changed().await?;
+    loop {
+        tokio::select! {
+            event = property_stream.next() => {
+                let Some(event) = event else {
+                    return Ok(());
+                };
+                if let Ok(powered) = event.get().await {
+                    send_bluetooth_state(sender, powered);
+                }
+            }
+            owner = owner_stream.next() => {
+                // BlueZ went away or got replaced, either way the adapter
+                // needs to be looked up again
+                if let Some(None) = owner {
+                    send_bluetooth_state(sender, false);
+                }
+                return Ok(());
+            }
+        }
+
+        if sender.is_closed() {
+            return Ok(());
         }
     }
+}
 
-    Ok(())
+/// Only notifies the receivers if the state actually changed.
+pub fn send_bluetooth_state(sender: &watch::Sender<bool>, powered: bool) {
+    sender.send_if_modified(|state| {
+        let is_modified = *state != powered;
+        *state = powered;
+        is_modified
+    });
 }
 
 pub async fn is_bluetooth_powered(conn: &zbus::Connection) -> zbus::Result<bool> {
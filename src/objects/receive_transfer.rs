@@ -12,6 +12,66 @@ pub enum UserAction {
     TransferCancel,
 }
 
+/// A time window during which incoming requests get answered automatically.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AutoResponseRule {
+    /// Days of the week the rule applies on, from 1 (Monday) to 7 (Sunday),
+    /// every day if empty
+    #[serde(default)]
+    pub days: Vec<u8>,
+    /// Local time as `HH:MM`
+    pub start: String,
+    /// Local time as `HH:MM`, exclusive. It's an overnight window if it comes
+    /// before `start`
+    pub end: String,
+    pub action: AutoResponseAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoResponseAction {
+    Accept,
+    Decline,
+}
+
+impl AutoResponseRule {
+    /// `weekday` goes from 1 (Monday) to 7 (Sunday).
+    pub fn matches(&self, weekday: u8, minute_of_day: u32) -> bool {
+        if !self.days.is_empty() && !self.days.contains(&weekday) {
+            return false;
+        }
+
+        let (Some(start), Some(end)) = (
+            parse_minute_of_day(&self.start),
+            parse_minute_of_day(&self.end),
+        ) else {
+            return false;
+        };
+
+        if start <= end {
+            (start..end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= start || minute_of_day < end
+        }
+    }
+}
+
+impl From<AutoResponseAction> for UserAction {
+    fn from(value: AutoResponseAction) -> Self {
+        match value {
+            AutoResponseAction::Accept => UserAction::ConsentAccept,
+            AutoResponseAction::Decline => UserAction::ConsentDecline,
+        }
+    }
+}
+
+fn parse_minute_of_day(s: &str) -> Option<u32> {
+    let (hour, minute) = s.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
+
 pub mod imp {
     use std::{cell::RefCell, rc::Rc};
 
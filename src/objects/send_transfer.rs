@@ -31,6 +31,55 @@ impl std::fmt::Display for EndpointInfo {
     }
 }
 
+/// Prefix of the IDs given to endpoints added by entering their address, as
+/// opposed to the ones found through discovery.
+const MANUAL_ENDPOINT_ID_PREFIX: &str = "manual:";
+
+/// Prefix of the IDs given to the stand-ins for pinned recipients that haven't
+/// been discovered.
+const PINNED_PLACEHOLDER_ID_PREFIX: &str = "pinned:";
+
+impl EndpointInfo {
+    /// An endpoint for a device that couldn't be discovered, but whose address
+    /// is known.
+    pub fn manual(addr: std::net::SocketAddrV4) -> Self {
+        Self(rqs_lib::EndpointInfo {
+            id: format!("{MANUAL_ENDPOINT_ID_PREFIX}{addr}"),
+            name: Some(addr.to_string()),
+            ip: Some(addr.ip().to_string()),
+            port: Some(addr.port().to_string()),
+            present: Some(true),
+            ..Default::default()
+        })
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.id.starts_with(MANUAL_ENDPOINT_ID_PREFIX)
+    }
+
+    /// A stand-in for a pinned recipient that isn't around, so that it's still
+    /// listed as unavailable until it's discovered.
+    pub fn pinned_placeholder(device_name: &str) -> Self {
+        Self(rqs_lib::EndpointInfo {
+            id: format!("{PINNED_PLACEHOLDER_ID_PREFIX}{device_name}"),
+            name: Some(device_name.into()),
+            present: None,
+            ..Default::default()
+        })
+    }
+
+    pub fn is_pinned_placeholder(&self) -> bool {
+        self.id.starts_with(PINNED_PLACEHOLDER_ID_PREFIX)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WifiData {
+    pub ssid: String,
+    pub password: String,
+    pub security_type: String,
+}
+
 #[derive(Debug, Clone, glib::Boxed)]
 #[boxed_type(name = "ChannelMessageBoxed", nullable)]
 pub struct ChannelMessage(pub rqs_lib::channel::ChannelMessage);
@@ -47,6 +96,16 @@ impl ChannelMessage {
             .unwrap_or(gettext("Unknown device"))
     }
 
+    /// [`Self::device_name`] made safe to show, see [`utils::sanitize_device_name`].
+    pub fn display_device_name(&self) -> String {
+        let device_name = utils::sanitize_device_name(&self.device_name());
+        if device_name.is_empty() {
+            gettext("Unknown device")
+        } else {
+            device_name
+        }
+    }
+
     pub fn files(&self) -> Option<&Vec<String>> {
         self.msg
             .as_client_unchecked()
@@ -96,6 +155,33 @@ impl ChannelMessage {
                 _ => None,
             })
     }
+
+    pub fn transferred_wifi_data(&self) -> Option<WifiData> {
+        self.msg
+            .as_client_unchecked()
+            .metadata
+            .as_ref()
+            .and_then(|meta| match &meta.payload {
+                Some(TransferPayload::Wifi {
+                    ssid,
+                    password,
+                    security_type,
+                }) => Some(WifiData {
+                    ssid: ssid.clone(),
+                    password: password.clone(),
+                    security_type: format!("{security_type:?}"),
+                }),
+                _ => None,
+            })
+    }
+}
+
+/// What's remembered about a recipient across sessions.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct RecipientHistoryEntry {
+    pub send_count: u32,
+    /// Seconds since the Unix epoch
+    pub last_sent: u64,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, glib::Boxed)]
@@ -111,9 +197,13 @@ pub enum TransferState {
 }
 
 pub mod imp {
-    use std::{cell::RefCell, rc::Rc};
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
 
     use gtk::glib::Properties;
+    use tokio_util::sync::CancellationToken;
 
     use super::*;
 
@@ -122,9 +212,20 @@ pub mod imp {
     pub struct SendTransferState {
         pub eta: Rc<RefCell<utils::DataTransferEta>>,
         pub files: Rc<RefCell<Vec<String>>>,
+        /// Armed while waiting for the receiver to start the transfer
+        pub send_start_timeout_ctk: RefCell<Option<CancellationToken>>,
+        /// Set when the request got cancelled for going unanswered
+        pub is_timed_out: Cell<bool>,
+        /// Armed for as long as the transfer is underway
+        pub connection_loss_ctk: RefCell<Option<CancellationToken>>,
+        /// Set when the transfer got cancelled for the network going away
+        pub is_connection_lost: Cell<bool>,
 
         #[property(get, set)]
         transfer_state: RefCell<TransferState>,
+        /// 1-based position in the send queue, 0 if not waiting in it
+        #[property(get, set)]
+        queue_position: Cell<u32>,
         #[property(get, set)]
         device_name: RefCell<String>,
 
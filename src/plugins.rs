@@ -1,18 +1,71 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 
-use anyhow::Context;
+use gettextrs::gettext;
+use gtk::glib;
 
 use crate::{
-    config::{DATADIR, PKGDATADIR},
-    utils::{is_file_same, xdg_data_dirs},
+    config::{APP_ID, DATADIR, PKGDATADIR},
+    utils::{is_file_same, write_atomic, xdg_data_dirs},
 };
 
+#[derive(Debug)]
+pub enum PluginError {
+    /// The plugin's own files weren't installed along with the app
+    FilesMissing(Vec<PathBuf>),
+    /// The file manager's directory for plugins couldn't be found
+    DirNotFound,
+    /// The file manager's directory for plugins can't be written to
+    DirNotWritable(std::io::Error),
+    /// Something the plugin needs at runtime isn't installed
+    DepsMissing(String),
+    /// A file shared with other plugins isn't in the expected format
+    Malformed(PathBuf),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FilesMissing(files) => write!(f, "Missing plugin files: {files:?}"),
+            Self::DirNotFound => write!(f, "Couldn't find the directory for the plugin"),
+            Self::DirNotWritable(err) => {
+                write!(f, "Couldn't write to the directory for the plugin: {err}")
+            }
+            Self::DepsMissing(deps) => write!(f, "Missing plugin dependencies: {deps}"),
+            Self::Malformed(path) => write!(f, "Malformed plugin file: {path:?}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DirNotWritable(err) | Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PluginError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => {
+                Self::DirNotWritable(err)
+            }
+            _ => Self::Io(err),
+        }
+    }
+}
+
 pub trait Plugin {
     /// Installs and updates the plugin.
     ///
     /// Run it under a separate thread if you don't want it to block.
-    fn install_plugin(&self) -> anyhow::Result<()>;
-    fn uninstall_plugin(&self) -> anyhow::Result<()>;
+    fn install_plugin(&self) -> Result<(), PluginError>;
+    fn uninstall_plugin(&self) -> Result<(), PluginError>;
 }
 
 pub trait FileBasedPlugin: Plugin {
@@ -22,23 +75,19 @@ pub trait FileBasedPlugin: Plugin {
     fn help_install_dir() -> &'static str;
 }
 impl<T: FileBasedPlugin> Plugin for T {
-    fn install_plugin(&self) -> anyhow::Result<()> {
+    fn install_plugin(&self) -> Result<(), PluginError> {
         let missing_plugin_files = self
             .plugin_files()
             .into_iter()
             .filter(|it| !it.exists())
+            .cloned()
             .collect::<Vec<_>>();
 
         if missing_plugin_files.len() > 0 {
-            anyhow::bail!("Missing plugin files: {:?}", missing_plugin_files);
+            return Err(PluginError::FilesMissing(missing_plugin_files));
         }
 
-        let install_dir = self.install_dir().with_context(|| {
-            anyhow::anyhow!(
-                "Couldn't find the directory to move the plugin files into: {:?}",
-                self.plugin_files()
-            )
-        })?;
+        let install_dir = self.install_dir().ok_or(PluginError::DirNotFound)?;
 
         tracing::debug!(
             plugin = std::any::type_name::<T>(),
@@ -76,13 +125,8 @@ impl<T: FileBasedPlugin> Plugin for T {
         Ok(())
     }
 
-    fn uninstall_plugin(&self) -> anyhow::Result<()> {
-        let install_dir = self.install_dir().with_context(|| {
-            anyhow::anyhow!(
-                "Couldn't find the directory to delete the plugin files from: {:?}",
-                self.plugin_files()
-            )
-        })?;
+    fn uninstall_plugin(&self) -> Result<(), PluginError> {
+        let install_dir = self.install_dir().ok_or(PluginError::DirNotFound)?;
 
         tracing::debug!(
             plugin = std::any::type_name::<T>(),
@@ -160,4 +204,325 @@ impl NautilusPlugin {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Checks whether the Python modules imported by the plugin script are
+    /// available, since Nautilus silently skips extensions that fail to load.
+    ///
+    /// Always succeeds under Flatpak, as the host's Python can't be probed
+    /// from within the sandbox.
+    ///
+    /// Run it under a separate thread if you don't want it to block.
+    pub fn check_runtime_deps(&self) -> Result<(), PluginError> {
+        if std::env::var("FLATPAK_ID").is_ok_and(|it| !it.is_empty()) {
+            return Ok(());
+        }
+
+        let output = std::process::Command::new("python3")
+            .args(["-c", "import gi; import dbus"])
+            .output()
+            .map_err(|err| PluginError::DepsMissing(format!("Couldn't run python3: {err}")))?;
+
+        if !output.status.success() {
+            return Err(PluginError::DepsMissing(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Installs a KDE ServiceMenu for Dolphin that opens the selected files with
+/// the app, which then get queued for sending.
+///
+/// Unlike [`NautilusPlugin`], the ServiceMenu is generated at install time
+/// since the command it runs depends on whether the app is running under
+/// Flatpak.
+#[derive(Debug, Clone, Default)]
+pub struct DolphinPlugin;
+
+impl DolphinPlugin {
+    const SERVICE_MENU_FILE_NAME: &'static str = "packet_dolphin.desktop";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn install_dir() -> Option<PathBuf> {
+        // Not using XDG_DATA_HOME since it points to the app's own data
+        // directory under Flatpak
+        dirs::home_dir().map(|it| it.join(".local/share/kio/servicemenus"))
+    }
+
+    /// It's the path to show to the user for troubleshooting purposes.
+    pub fn help_install_dir() -> &'static str {
+        "~/.local/share/kio/servicemenus"
+    }
+
+    fn exec_command() -> String {
+        match std::env::var("FLATPAK_ID") {
+            // File forwarding grants the app access to the selected files
+            // through the document portal
+            Ok(flatpak_id) if !flatpak_id.is_empty() => {
+                format!("flatpak run --file-forwarding {flatpak_id} @@ %F @@")
+            }
+            _ => "packet %F".into(),
+        }
+    }
+
+    fn service_menu_contents() -> String {
+        format!(
+            "[Desktop Entry]\n\
+            Type=Service\n\
+            MimeType=application/octet-stream;\n\
+            X-KDE-ServiceTypes=KonqPopupMenu/Plugin\n\
+            Actions=SendWithPacket;\n\
+            \n\
+            [Desktop Action SendWithPacket]\n\
+            Name={}\n\
+            Icon={APP_ID}\n\
+            Exec={}\n",
+            gettext("Send with Packet"),
+            Self::exec_command()
+        )
+    }
+}
+
+impl Plugin for DolphinPlugin {
+    fn install_plugin(&self) -> Result<(), PluginError> {
+        let install_dir = Self::install_dir().ok_or(PluginError::DirNotFound)?;
+        let dest_path = install_dir.join(Self::SERVICE_MENU_FILE_NAME);
+
+        tracing::debug!(
+            plugin = std::any::type_name::<Self>(),
+            ?dest_path,
+            "Installing plugin"
+        );
+
+        fs_err::create_dir_all(&install_dir)?;
+
+        let contents = Self::service_menu_contents();
+        if fs_err::read_to_string(&dest_path).ok().as_ref() != Some(&contents) {
+            tracing::debug!(?dest_path, "Writing service menu");
+            fs_err::write(&dest_path, contents)?;
+        }
+
+        // KIO ignores service menus in the user's data directory unless
+        // they're marked as executable
+        #[cfg(unix)]
+        fs_err::set_permissions(&dest_path, std::fs::Permissions::from_mode(0o755))?;
+
+        Ok(())
+    }
+
+    fn uninstall_plugin(&self) -> Result<(), PluginError> {
+        let dest_path = Self::install_dir()
+            .ok_or(PluginError::DirNotFound)?
+            .join(Self::SERVICE_MENU_FILE_NAME);
+
+        tracing::debug!(
+            plugin = std::any::type_name::<Self>(),
+            ?dest_path,
+            "Uninstalling plugin"
+        );
+
+        if dest_path.is_file() {
+            tracing::debug!(file_path = ?dest_path, "Removing plugin file");
+            fs_err::remove_file(dest_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NemoPlugin {
+    files: Vec<PathBuf>,
+}
+
+impl FileBasedPlugin for NemoPlugin {
+    fn plugin_files(&self) -> &[PathBuf] {
+        self.files.as_slice()
+    }
+
+    fn install_dir(&self) -> Option<PathBuf> {
+        // https://github.com/linuxmint/nemo/blob/master/files/usr/share/nemo/actions/sample.nemo_action
+        let mut base_dirs = vec![];
+        if let Some(data_home_dir) = std::env::var_os("XDG_DATA_HOME")
+            .and_then(|it| (!it.is_empty()).then_some(PathBuf::from(it)))
+        {
+            base_dirs.push(data_home_dir);
+        }
+        // XDG_DATA_HOME points to the app's own data directory under Flatpak
+        if let Some(home) = dirs::home_dir() {
+            base_dirs.insert(0, home.join(".local/share"));
+        }
+
+        base_dirs
+            .into_iter()
+            .map(|it| it.join("nemo/actions"))
+            .find(|it| it.is_dir())
+    }
+
+    fn help_install_dir() -> &'static str {
+        "~/.local/share/nemo/actions"
+    }
+}
+
+impl Default for NemoPlugin {
+    fn default() -> Self {
+        Self {
+            files: vec![PathBuf::from(PKGDATADIR).join("plugins/packet.nemo_action")],
+        }
+    }
+}
+
+impl NemoPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Adds a custom action to Thunar's `uca.xml`.
+///
+/// Thunar keeps all of the custom actions in that one file, so the action is
+/// spliced in and out of it instead of being copied over like the other
+/// plugins.
+#[derive(Debug, Clone, Default)]
+pub struct ThunarPlugin;
+
+impl ThunarPlugin {
+    const UNIQUE_ID: &'static str = "packet-send-files";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn config_dir() -> Option<PathBuf> {
+        let mut base_dirs = vec![];
+        if let Some(config_home_dir) = std::env::var_os("XDG_CONFIG_HOME")
+            .and_then(|it| (!it.is_empty()).then_some(PathBuf::from(it)))
+        {
+            base_dirs.push(config_home_dir);
+        }
+        // XDG_CONFIG_HOME points to the app's own config directory under Flatpak
+        if let Some(home) = dirs::home_dir() {
+            base_dirs.insert(0, home.join(".config"));
+        }
+
+        base_dirs
+            .into_iter()
+            .map(|it| it.join("Thunar"))
+            .find(|it| it.is_dir())
+    }
+
+    /// It's the path to show to the user for troubleshooting purposes.
+    pub fn help_install_dir() -> &'static str {
+        "~/.config/Thunar"
+    }
+
+    fn action_xml() -> String {
+        format!(
+            "<action>\n\
+            \t<icon>{APP_ID}</icon>\n\
+            \t<name>{}</name>\n\
+            \t<submenu></submenu>\n\
+            \t<unique-id>{}</unique-id>\n\
+            \t<command>gapplication launch {APP_ID} %F</command>\n\
+            \t<description>{}</description>\n\
+            \t<range>*</range>\n\
+            \t<patterns>*</patterns>\n\
+            \t<audio-files/>\n\
+            \t<image-files/>\n\
+            \t<other-files/>\n\
+            \t<text-files/>\n\
+            \t<video-files/>\n\
+            </action>\n",
+            glib::markup_escape_text(&gettext("Send with Packet")),
+            Self::UNIQUE_ID,
+            glib::markup_escape_text(&gettext("Send the selected files to a nearby device")),
+        )
+    }
+
+    /// Returns the byte range of our `<action>` element within `uca.xml`.
+    fn find_action(contents: &str) -> Option<std::ops::Range<usize>> {
+        let unique_id_idx =
+            contents.find(&format!("<unique-id>{}</unique-id>", Self::UNIQUE_ID))?;
+        let start = contents[..unique_id_idx].rfind("<action>")?;
+        let end = unique_id_idx + contents[unique_id_idx..].find("</action>")? + "</action>".len();
+
+        // Also take the trailing newline so that uninstalling doesn't leave
+        // blank lines behind
+        let end = if contents[end..].starts_with('\n') {
+            end + 1
+        } else {
+            end
+        };
+
+        Some(start..end)
+    }
+}
+
+impl Plugin for ThunarPlugin {
+    fn install_plugin(&self) -> Result<(), PluginError> {
+        let uca_path = Self::config_dir()
+            .ok_or(PluginError::DirNotFound)?
+            .join("uca.xml");
+
+        tracing::debug!(
+            plugin = std::any::type_name::<Self>(),
+            ?uca_path,
+            "Installing plugin"
+        );
+
+        let action = Self::action_xml();
+        let mut contents = if uca_path.exists() {
+            fs_err::read_to_string(&uca_path)?
+        } else {
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<actions>\n</actions>\n".into()
+        };
+
+        if let Some(range) = Self::find_action(&contents) {
+            if contents[range.clone()] == action {
+                return Ok(());
+            }
+            contents.replace_range(range, "");
+        }
+
+        let insert_idx = contents
+            .rfind("</actions>")
+            .ok_or_else(|| PluginError::Malformed(uca_path.clone()))?;
+        contents.insert_str(insert_idx, &action);
+
+        tracing::debug!(?uca_path, "Writing custom action");
+        // The file holds the user's other custom actions too
+        write_atomic(&uca_path, contents)?;
+
+        Ok(())
+    }
+
+    fn uninstall_plugin(&self) -> Result<(), PluginError> {
+        let uca_path = Self::config_dir()
+            .ok_or(PluginError::DirNotFound)?
+            .join("uca.xml");
+
+        tracing::debug!(
+            plugin = std::any::type_name::<Self>(),
+            ?uca_path,
+            "Uninstalling plugin"
+        );
+
+        if !uca_path.is_file() {
+            return Ok(());
+        }
+
+        let mut contents = fs_err::read_to_string(&uca_path)?;
+        if let Some(range) = Self::find_action(&contents) {
+            tracing::debug!(?uca_path, "Removing custom action");
+            contents.replace_range(range, "");
+            write_atomic(&uca_path, contents)?;
+        }
+
+        Ok(())
+    }
 }
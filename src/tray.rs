@@ -2,17 +2,68 @@ use gettextrs::gettext;
 
 use crate::config::APP_ID;
 
-#[derive(Debug)]
-pub struct Tray {
-    pub tx: tokio::sync::mpsc::Sender<TrayMessage>,
-}
+use std::pin::Pin;
 
 #[derive(Debug, Clone)]
 pub enum TrayMessage {
     OpenWindow,
+    OpenPreferences,
+    ToggleVisibility,
     Quit,
 }
 
+/// The app state that's reflected in the tray menu.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrayState {
+    pub is_visible: bool,
+}
+
+/// A system tray icon whose menu items report back through [`TrayMessage`]s.
+pub trait TrayBackend {
+    fn spawn(
+        tx: tokio::sync::mpsc::Sender<TrayMessage>,
+    ) -> impl Future<Output = anyhow::Result<Self>>
+    where
+        Self: Sized;
+    fn shutdown(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>>;
+    fn update(&mut self, state: TrayState);
+}
+
+/// The tray backend for the platform being built for.
+#[cfg(target_os = "linux")]
+pub type PlatformTray = KsniTray;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub type PlatformTray = TrayIconTray;
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub type PlatformTray = NoopTray;
+
+/// For platforms without a supported tray, so that the rest of the app doesn't
+/// have to care.
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub struct NoopTray;
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+impl TrayBackend for NoopTray {
+    async fn spawn(_tx: tokio::sync::mpsc::Sender<TrayMessage>) -> anyhow::Result<Self> {
+        tracing::debug!("No tray icon support on this platform");
+        Ok(Self)
+    }
+
+    fn shutdown(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async {})
+    }
+
+    fn update(&mut self, _state: TrayState) {}
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct Tray {
+    pub tx: tokio::sync::mpsc::Sender<TrayMessage>,
+    pub state: TrayState,
+}
+
+#[cfg(target_os = "linux")]
 impl ksni::Tray for Tray {
     fn id(&self) -> String {
         APP_ID.into()
@@ -34,6 +85,24 @@ impl ksni::Tray for Tray {
                 ..Default::default()
             }
             .into(),
+            StandardItem {
+                label: gettext("Preferences"),
+                activate: Box::new(move |this: &mut Self| {
+                    _ = this.tx.try_send(TrayMessage::OpenPreferences);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            CheckmarkItem {
+                label: gettext("Visible"),
+                checked: self.state.is_visible,
+                activate: Box::new(move |this: &mut Self| {
+                    _ = this.tx.try_send(TrayMessage::ToggleVisibility);
+                }),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
             StandardItem {
                 label: gettext("Exit"),
                 icon_name: "application-exit-symbolic".into(),
@@ -46,3 +115,162 @@ impl ksni::Tray for Tray {
         ]
     }
 }
+
+/// There's `tray-icon` for cross-platform systray support but on linux it still
+/// relies on gtk3 which doesn't work with gtk4 environment, so KStatusNotifierItem
+/// is used instead.
+///
+/// https://github.com/tauri-apps/tray-icon/pull/201
+#[cfg(target_os = "linux")]
+pub struct KsniTray(ksni::Handle<Tray>);
+
+#[cfg(target_os = "linux")]
+impl TrayBackend for KsniTray {
+    async fn spawn(tx: tokio::sync::mpsc::Sender<TrayMessage>) -> anyhow::Result<Self> {
+        use ksni::TrayMethods;
+
+        let tray = Tray {
+            tx,
+            state: Default::default(),
+        };
+        let handle = if ashpd::is_sandboxed().await {
+            tray.spawn_without_dbus_name().await
+        } else {
+            tray.spawn().await
+        }
+        .map_err(|err| anyhow::anyhow!("Failed to setup KStatusNotifierItem tray icon: {err}"))?;
+
+        Ok(Self(handle))
+    }
+
+    fn shutdown(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(async move {
+            self.0.shutdown().await;
+        })
+    }
+
+    fn update(&mut self, state: TrayState) {
+        let handle = self.0.clone();
+        gtk::glib::spawn_future_local(async move {
+            handle.update(move |tray| tray.state = state).await;
+        });
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub struct TrayIconTray {
+    _tray_icon: tray_icon::TrayIcon,
+    visibility_item: tray_icon::menu::CheckMenuItem,
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+impl TrayBackend for TrayIconTray {
+    async fn spawn(tx: tokio::sync::mpsc::Sender<TrayMessage>) -> anyhow::Result<Self> {
+        use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+
+        let open_item = MenuItem::new(gettext("Open"), true, None);
+        let preferences_item = MenuItem::new(gettext("Preferences"), true, None);
+        let visibility_item = CheckMenuItem::new(gettext("Visible"), true, false, None);
+        let quit_item = MenuItem::new(gettext("Exit"), true, None);
+        let menu = Menu::new();
+        menu.append_items(&[
+            &open_item,
+            &preferences_item,
+            &visibility_item,
+            &PredefinedMenuItem::separator(),
+            &quit_item,
+        ])?;
+
+        let (open_id, preferences_id, visibility_id, quit_id) = (
+            open_item.id().clone(),
+            preferences_item.id().clone(),
+            visibility_item.id().clone(),
+            quit_item.id().clone(),
+        );
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            let msg = if event.id == open_id {
+                TrayMessage::OpenWindow
+            } else if event.id == preferences_id {
+                TrayMessage::OpenPreferences
+            } else if event.id == visibility_id {
+                TrayMessage::ToggleVisibility
+            } else if event.id == quit_id {
+                TrayMessage::Quit
+            } else {
+                return;
+            };
+            _ = tx.try_send(msg);
+        }));
+
+        let tray_icon = tray_icon::TrayIconBuilder::new()
+            .with_id(APP_ID)
+            .with_tooltip(gettext("Packet"))
+            .with_icon(load_app_icon()?)
+            .with_menu(Box::new(menu))
+            .build()?;
+
+        Ok(Self {
+            _tray_icon: tray_icon,
+            visibility_item,
+        })
+    }
+
+    fn shutdown(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        // The icon itself goes away once dropped
+        tray_icon::menu::MenuEvent::set_event_handler(None::<fn(tray_icon::menu::MenuEvent)>);
+        Box::pin(async {})
+    }
+
+    fn update(&mut self, state: TrayState) {
+        // The item toggles itself on click, this keeps it in line with the
+        // actual state
+        self.visibility_item.set_checked(state.is_visible);
+    }
+}
+
+/// `tray-icon` only takes raw RGBA pixels, so the app icon from the icon theme
+/// is rendered into those.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn load_app_icon() -> anyhow::Result<tray_icon::Icon> {
+    use anyhow::Context;
+    use gtk::{gdk, gsk, prelude::*};
+
+    const ICON_SIZE: i32 = 32;
+
+    let display = gdk::Display::default().context("Couldn't get the default display")?;
+    let paintable = gtk::IconTheme::for_display(&display).lookup_icon(
+        APP_ID,
+        &[],
+        ICON_SIZE,
+        1,
+        gtk::TextDirection::None,
+        gtk::IconLookupFlags::empty(),
+    );
+
+    let snapshot = gtk::Snapshot::new();
+    paintable.snapshot(&snapshot, ICON_SIZE as f64, ICON_SIZE as f64);
+    let node = snapshot.to_node().context("Couldn't render the app icon")?;
+
+    let renderer = gsk::CairoRenderer::new();
+    renderer.realize_for_display(&display)?;
+    let texture = renderer.render_texture(&node, None);
+    renderer.unrealize();
+
+    let mut downloader = gdk::TextureDownloader::new(&texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8);
+    let (bytes, stride) = downloader.download_bytes();
+
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+    let rgba = bytes
+        .chunks(stride)
+        .take(height)
+        .flat_map(|row| &row[..width * 4])
+        .copied()
+        .collect::<Vec<_>>();
+
+    Ok(tray_icon::Icon::from_rgba(
+        rgba,
+        width as u32,
+        height as u32,
+    )?)
+}
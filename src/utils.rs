@@ -1,13 +1,15 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     fmt,
-    io::Read,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
     time::{self},
 };
 
 use ashpd::desktop::notification::Notification;
-use gettextrs::ngettext;
+use formatx::formatx;
+use gettextrs::{gettext, ngettext};
 use gtk::glib::{self};
 
 #[macro_export]
@@ -95,7 +97,33 @@ where
     }
 }
 
+/// Ids of the notifications that were sent and not removed yet, so that they
+/// can all be cleared on exit instead of outlasting the app.
+#[derive(Debug, Default)]
+pub struct NotificationRegistry(Mutex<HashSet<String>>);
+
+impl NotificationRegistry {
+    pub fn global() -> &'static Self {
+        static NOTIFICATION_REGISTRY: OnceLock<NotificationRegistry> = OnceLock::new();
+        NOTIFICATION_REGISTRY.get_or_init(Default::default)
+    }
+
+    fn register(&self, id: &str) {
+        self.0.lock().unwrap().insert(id.to_string());
+    }
+
+    fn deregister(&self, id: &str) {
+        self.0.lock().unwrap().remove(id);
+    }
+
+    fn take_all(&self) -> Vec<String> {
+        self.0.lock().unwrap().drain().collect()
+    }
+}
+
 pub fn spawn_notification(id: String, notification: Notification) {
+    NotificationRegistry::global().register(&id);
+
     glib::spawn_future_local(async move {
         _ = async move || -> anyhow::Result<()> {
             use ashpd::desktop::notification::*;
@@ -110,6 +138,8 @@ pub fn spawn_notification(id: String, notification: Notification) {
 }
 
 pub fn remove_notification(id: String) {
+    NotificationRegistry::global().deregister(&id);
+
     glib::spawn_future_local(async move {
         _ = async move || -> anyhow::Result<()> {
             use ashpd::desktop::notification::*;
@@ -123,6 +153,29 @@ pub fn remove_notification(id: String) {
     });
 }
 
+/// Removes every notification the app has sent that's still around.
+pub fn remove_all_notifications() {
+    let ids = NotificationRegistry::global().take_all();
+    if ids.is_empty() {
+        return;
+    }
+
+    tracing::debug!(count = ids.len(), "Removing lingering notifications");
+    glib::spawn_future_local(async move {
+        _ = async move || -> anyhow::Result<()> {
+            use ashpd::desktop::notification::*;
+            let proxy = NotificationProxy::new().await?;
+
+            for id in ids {
+                proxy.remove_notification(&id).await?;
+            }
+
+            Ok(())
+        }()
+        .await;
+    });
+}
+
 pub fn strip_user_home_prefix<P: AsRef<Path>>(path: P) -> PathBuf {
     if let Some(home) = dirs::home_dir()
         && let Ok(stripped) = path.as_ref().strip_prefix(&home)
@@ -133,6 +186,174 @@ pub fn strip_user_home_prefix<P: AsRef<Path>>(path: P) -> PathBuf {
     path.as_ref().into()
 }
 
+/// Longest device name shown as is, in characters. Peers can send names of
+/// any length.
+const DEVICE_NAME_DISPLAY_MAX_CHARS: usize = 48;
+
+/// Bidirectional formatting characters, which can make the text around a name
+/// read differently than it's laid out, e.g. U+202E RIGHT-TO-LEFT OVERRIDE.
+fn is_bidi_formatting_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Makes a device name sent by a peer fit for display, by dropping control
+/// and bidi formatting characters, and cutting names that are too long short.
+///
+/// Meant only for display, the raw name should still be used for matching and
+/// logging. Might return an empty string.
+pub fn sanitize_device_name(name: &str) -> String {
+    let name = name
+        .chars()
+        // So that e.g. a newline doesn't glue two words together
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .filter(|c| !c.is_control() && !is_bidi_formatting_char(*c))
+        .collect::<String>();
+    let name = name.trim();
+
+    if name.chars().count() > DEVICE_NAME_DISPLAY_MAX_CHARS {
+        let truncated = name
+            .chars()
+            .take(DEVICE_NAME_DISPLAY_MAX_CHARS)
+            .collect::<String>();
+        format!("{}…", truncated.trim_end())
+    } else {
+        name.to_string()
+    }
+}
+
+/// Checks whether files can be created in `path` by creating and removing a
+/// probe file, since the permission bits won't reflect e.g. an unmounted drive
+/// or a revoked portal permission.
+pub fn is_folder_writable(path: impl AsRef<Path>) -> bool {
+    let probe_path = path
+        .as_ref()
+        .join(format!(".packet-write-probe-{}", std::process::id()));
+
+    match fs_err::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe_path)
+    {
+        Ok(_) => {
+            _ = fs_err::remove_file(&probe_path)
+                .inspect_err(|err| tracing::warn!(%err, "Couldn't remove the write probe"));
+            true
+        }
+        Err(err) => {
+            tracing::warn!(%err, "Folder isn't writable");
+            false
+        }
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so
+/// that `path` is never left half written, e.g. if the app is killed midway.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomic_with(path.as_ref(), |file| file.write_all(contents.as_ref()))
+}
+
+fn atomic_tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_file_name = std::ffi::OsString::from(".");
+    tmp_file_name.push(path.file_name().unwrap_or_default());
+    tmp_file_name.push(format!(".tmp-{}", std::process::id()));
+    path.with_file_name(tmp_file_name)
+}
+
+fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut fs_err::File) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp_path = atomic_tmp_path(path);
+
+    let result = (|| {
+        let mut file = fs_err::File::create(&tmp_path)?;
+        write(&mut file)?;
+        // Otherwise the rename might reach the disk before the contents do
+        file.sync_all()?;
+        fs_err::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        _ = fs_err::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Parses JSON state, falling back to the default if it's invalid, e.g. after
+/// being edited by hand. `what` names the state in the warning.
+pub fn json_or_default<T>(json: &str, what: &str) -> T
+where
+    T: serde::de::DeserializeOwned + Default,
+{
+    serde_json::from_str(json)
+        .inspect_err(|err| tracing::warn!(%err, "Invalid {what}"))
+        .unwrap_or_default()
+}
+
+/// Names of the network interfaces on the system, excluding loopback.
+pub fn network_interface_names() -> Vec<String> {
+    let mut names = fs_err::read_dir("/sys/class/net")
+        .inspect_err(|err| tracing::warn!(%err, "Couldn't list the network interfaces"))
+        .map(|entries| {
+            entries
+                .filter_map(|it| it.ok())
+                .filter_map(|it| it.file_name().into_string().ok())
+                .filter(|it| it != "lo")
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    names
+}
+
+/// Whether any network interface besides loopback is up. All of them being down
+/// is what airplane mode looks like.
+pub fn has_active_network_interface() -> bool {
+    network_interface_names().iter().any(|name| {
+        fs_err::read_to_string(Path::new("/sys/class/net").join(name).join("operstate"))
+            .is_ok_and(|it| it.trim() == "up")
+    })
+}
+
+/// Runs `f` until it succeeds or `max_attempts` are exhausted, waiting between
+/// the attempts with the delay doubling each time, starting at `initial_delay`.
+pub async fn retry_with_backoff<T, F, Fut>(
+    max_attempts: u32,
+    initial_delay: time::Duration,
+    mut f: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut delay = initial_delay;
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(it) => return Ok(it),
+            Err(err) if attempt < max_attempts => {
+                tracing::warn!(
+                    attempt,
+                    max_attempts,
+                    ?delay,
+                    "Attempt failed, retrying: {err:#}"
+                );
+                futures_timer::Delay::new(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => {
+                tracing::warn!(attempt, max_attempts, "Giving up after the last attempt");
+                return Err(err);
+            }
+        }
+    }
+}
+
 /// Flatpak uses get_user_special_dir to get xdg directories, and so if it fails
 /// due to there being no `XDG_DOWNLOAD_DIR` and `user-dirs.dirs`, Flatpak will simply
 /// refuse to mount xdg-download in the sandbox. Leaving us with nothing.
@@ -183,8 +404,91 @@ pub fn xdg_download_with_fallback() -> PathBuf {
     }
 }
 
+/// A log file that gets rotated once it grows past `max_size`, keeping up to
+/// `max_rotated` of the older ones as `<name>.1`, `<name>.2` and so on, the
+/// lower being the more recent.
+///
+/// `tracing_appender::rolling` can only rotate based on time.
+#[derive(Debug)]
+pub struct RotatingLogFile {
+    path: PathBuf,
+    file: fs_err::File,
+    size: u64,
+    max_size: u64,
+    max_rotated: usize,
+}
+
+impl RotatingLogFile {
+    /// Truncates the file if it already exists.
+    pub fn create(path: impl Into<PathBuf>, max_size: u64, max_rotated: usize) -> io::Result<Self> {
+        let path = path.into();
+        let file = fs_err::File::create(&path)?;
+
+        Ok(Self {
+            path,
+            file,
+            size: 0,
+            max_size,
+            max_rotated,
+        })
+    }
+
+    fn rotated_path(&self, idx: usize) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_owned();
+        file_name.push(format!(".{idx}"));
+        self.path.with_file_name(file_name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for idx in (1..self.max_rotated).rev() {
+            let rotated_path = self.rotated_path(idx);
+            if rotated_path.exists() {
+                fs_err::rename(&rotated_path, self.rotated_path(idx + 1))?;
+            }
+        }
+        if self.max_rotated > 0 {
+            fs_err::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = fs_err::File::create(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+impl io::Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
 const STEPS_TRACK_COUNT: usize = 5;
 
+/// How the per-second transfer speeds are combined into the speed used for
+/// the estimate.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum EtaSmoothing {
+    /// Simple mean over the tracked window
+    #[default]
+    Mean,
+    /// Exponential moving average, `alpha` in `(0, 1]` is the weight of the
+    /// latest second. Lower values react slower but jump around less on
+    /// bursty networks.
+    ExponentialMovingAverage { alpha: f64 },
+}
+
 /// Proudly stolen from:\
 /// https://github.com/Manishearth/rustup.rs/blob/1.0.0/src/rustup-cli/download_tracker.rs
 #[derive(Debug, Clone, better_default::Default)]
@@ -202,6 +506,11 @@ pub struct DataTransferEta {
 
     last_sec: Option<time::Instant>,
     seconds_elapsed: usize,
+
+    #[default(STEPS_TRACK_COUNT)]
+    window_size: usize,
+    smoothing: EtaSmoothing,
+    ema_speed: Option<f64>,
 }
 
 impl DataTransferEta {
@@ -212,13 +521,30 @@ impl DataTransferEta {
         }
     }
 
+    /// Averages the speed over the last `n` seconds instead of the default 5.
+    pub fn with_window(n: usize) -> Self {
+        let window_size = n.max(1);
+        Self {
+            window_size,
+            transferred_last_few_secs: VecDeque::with_capacity(window_size),
+            ..Default::default()
+        }
+    }
+
+    pub fn smoothing(mut self, smoothing: EtaSmoothing) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
     pub fn step_with(&mut self, total_transferred: usize) {
+        self.step_with_at(total_transferred, time::Instant::now());
+    }
+
+    fn step_with_at(&mut self, total_transferred: usize, current_time: time::Instant) {
         let len = total_transferred - self.total_transferred;
         self.transferred_this_sec += len;
         self.total_transferred = total_transferred;
 
-        let current_time = time::Instant::now();
-
         match self.last_sec {
             None => {
                 self.last_sec = Some(current_time);
@@ -230,11 +556,20 @@ impl DataTransferEta {
                     self.seconds_elapsed += 1;
 
                     self.last_sec = Some(current_time);
-                    if self.transferred_last_few_secs.len() == STEPS_TRACK_COUNT {
+                    if self.transferred_last_few_secs.len() == self.window_size {
                         self.transferred_last_few_secs.pop_back();
                     }
                     self.transferred_last_few_secs
                         .push_front(self.transferred_this_sec);
+
+                    if let EtaSmoothing::ExponentialMovingAverage { alpha } = self.smoothing {
+                        let speed = self.transferred_this_sec as f64;
+                        self.ema_speed = Some(match self.ema_speed {
+                            Some(prev_speed) => alpha * speed + (1. - alpha) * prev_speed,
+                            None => speed,
+                        });
+                    }
+
                     self.transferred_this_sec = 0;
                 }
             }
@@ -250,15 +585,26 @@ impl DataTransferEta {
         self.transferred_last_few_secs.clear();
         self.seconds_elapsed = 0;
         self.last_sec = None;
+        self.ema_speed = None;
+    }
+
+    /// Bytes per second, as combined according to the smoothing.
+    fn speed(&self) -> f64 {
+        match self.smoothing {
+            EtaSmoothing::Mean => {
+                let sum = self
+                    .transferred_last_few_secs
+                    .iter()
+                    .fold(0., |a, &v| a + v as f64);
+                let len = self.transferred_last_few_secs.len();
+                if len > 0 { sum / len as f64 } else { 0. }
+            }
+            EtaSmoothing::ExponentialMovingAverage { .. } => self.ema_speed.unwrap_or(0.),
+        }
     }
 
     pub fn get_estimate_string(&self) -> String {
-        let sum = self
-            .transferred_last_few_secs
-            .iter()
-            .fold(0., |a, &v| a + v as f64);
-        let len = self.transferred_last_few_secs.len();
-        let speed = if len > 0 { sum / len as f64 } else { 0. };
+        let speed = self.speed();
 
         let total_len = self.total_len as f64;
         let remaining = total_len - self.total_transferred as f64;
@@ -268,6 +614,85 @@ impl DataTransferEta {
     }
 }
 
+/// e.g. "2 hours ago", going no coarser than days. Times in the future, from
+/// the clock having been changed, are taken as just now.
+pub fn format_relative_time(then: time::SystemTime) -> String {
+    format_relative_time_at(then, time::SystemTime::now())
+}
+
+fn format_relative_time_at(then: time::SystemTime, now: time::SystemTime) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    let elapsed_secs = now.duration_since(then).unwrap_or_default().as_secs();
+
+    let formatted = if elapsed_secs < 10 {
+        return gettext("just now");
+    } else if elapsed_secs < MINUTE {
+        let secs = elapsed_secs as u32;
+        formatx!(ngettext("{} second ago", "{} seconds ago", secs), secs)
+    } else if elapsed_secs < HOUR {
+        let mins = (elapsed_secs / MINUTE) as u32;
+        formatx!(ngettext("{} minute ago", "{} minutes ago", mins), mins)
+    } else if elapsed_secs < DAY {
+        let hours = (elapsed_secs / HOUR) as u32;
+        formatx!(ngettext("{} hour ago", "{} hours ago", hours), hours)
+    } else {
+        let days = (elapsed_secs / DAY) as u32;
+        formatx!(ngettext("{} day ago", "{} days ago", days), days)
+    };
+
+    formatted.unwrap_or_else(|_| "badly formatted locale string".into())
+}
+
+/// The decimal separator GLib formatted `1.5` with in `formatted`, e.g. `","`
+/// from "1,5 kB".
+fn decimal_separator_of(formatted: &str) -> Option<&str> {
+    let rest = formatted.trim_start().strip_prefix('1')?;
+    let end = rest.find('5')?;
+    (end > 0).then(|| &rest[..end])
+}
+
+/// e.g. "1.5 MB" or "1,5 MB" going by the locale, with decimal units like
+/// [`glib::format_size`] but pluralizing the bytes as well.
+pub fn format_size_localized(bytes: u64) -> String {
+    // GLib formats numbers the locale's way, so the separator is taken from it
+    let sample = glib::format_size(1500);
+    let decimal_separator = decimal_separator_of(&sample).unwrap_or(".");
+
+    format_size_with_separator(bytes, decimal_separator)
+}
+
+fn format_size_with_separator(bytes: u64, decimal_separator: &str) -> String {
+    const UNIT: f64 = 1000.;
+
+    let formatted = if bytes < UNIT as u64 {
+        formatx!(ngettext("{} byte", "{} bytes", bytes as u32), bytes)
+    } else {
+        let mut size = bytes as f64 / UNIT;
+        let mut unit_idx = 0;
+        // Going by the rounded size, so it's "1.0 MB" rather than "1000.0 kB"
+        while (size * 10.).round() / 10. >= UNIT && unit_idx < 4 {
+            size /= UNIT;
+            unit_idx += 1;
+        }
+
+        let size = format!("{size:.1}").replacen('.', decimal_separator, 1);
+        let unit_format = match unit_idx {
+            // Translators: A size, e.g. "1.5 kB"
+            0 => gettext("{} kB"),
+            1 => gettext("{} MB"),
+            2 => gettext("{} GB"),
+            3 => gettext("{} TB"),
+            _ => gettext("{} PB"),
+        };
+        formatx!(unit_format, size)
+    };
+
+    formatted.unwrap_or_else(|_| "badly formatted locale string".into())
+}
+
 #[derive(Debug, Clone, Copy)]
 struct HumanReadable(f64);
 
@@ -310,3 +735,301 @@ impl fmt::Display for HumanReadable {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh folder for a test to work in, removed by the test itself.
+    fn test_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("packet-test-{}-{name}", std::process::id()));
+        _ = fs_err::remove_dir_all(&path);
+        fs_err::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// Steps through a transfer that sent `per_sec[i]` bytes in its `i`th
+    /// second, returning the speed after each of them.
+    fn step_speeds(eta: &mut DataTransferEta, per_sec: &[usize]) -> Vec<f64> {
+        let start = time::Instant::now();
+        let mut total_transferred = 0;
+        eta.step_with_at(total_transferred, start);
+
+        per_sec
+            .iter()
+            .enumerate()
+            .map(|(idx, bytes)| {
+                total_transferred += bytes;
+                eta.step_with_at(
+                    total_transferred,
+                    start + time::Duration::from_secs(idx as u64 + 1),
+                );
+                eta.speed()
+            })
+            .collect()
+    }
+
+    fn spread(speeds: &[f64]) -> f64 {
+        let max = speeds.iter().copied().fold(f64::MIN, f64::max);
+        let min = speeds.iter().copied().fold(f64::MAX, f64::min);
+        max - min
+    }
+
+    #[test]
+    fn eta_default_is_mean_over_five_seconds() {
+        let mut eta = DataTransferEta::new(10_000);
+        assert_eq!(eta.window_size, STEPS_TRACK_COUNT);
+
+        let speeds = step_speeds(&mut eta, &[100, 200, 300, 400, 500, 600]);
+        // The first second drops out of the window
+        assert_eq!(speeds.last().copied(), Some(400.));
+    }
+
+    #[test]
+    fn eta_window_size() {
+        let mut eta = DataTransferEta::with_window(3);
+        let speeds = step_speeds(&mut eta, &[100, 200, 300, 400, 500]);
+        assert_eq!(speeds.last().copied(), Some(400.));
+
+        // Clamped so that there's always something to average
+        let mut eta = DataTransferEta::with_window(0);
+        let speeds = step_speeds(&mut eta, &[100, 200]);
+        assert_eq!(speeds.last().copied(), Some(200.));
+    }
+
+    #[test]
+    fn eta_ema_is_steadier_on_bursty_transfers() {
+        let bursty = [100, 0].repeat(20);
+
+        let mut mean_eta = DataTransferEta::new(10_000);
+        let mean_speeds = step_speeds(&mut mean_eta, &bursty);
+
+        let mut ema_eta = DataTransferEta::new(10_000)
+            .smoothing(EtaSmoothing::ExponentialMovingAverage { alpha: 0.2 });
+        let ema_speeds = step_speeds(&mut ema_eta, &bursty);
+
+        // Past the warm up, both settle around the actual average speed
+        let (mean_speeds, ema_speeds) = (&mean_speeds[20..], &ema_speeds[20..]);
+        assert!(ema_speeds.iter().all(|it| (40. ..=60.).contains(it)));
+        assert!(spread(ema_speeds) < spread(mean_speeds));
+    }
+
+    #[test]
+    fn eta_ema_follows_steady_transfers() {
+        let mut eta = DataTransferEta::new(10_000)
+            .smoothing(EtaSmoothing::ExponentialMovingAverage { alpha: 0.3 });
+        let speeds = step_speeds(&mut eta, &[250; 10]);
+        assert!(speeds.iter().all(|it| *it == 250.));
+    }
+
+    #[test]
+    fn writable_folder() {
+        let dir = test_dir("writable-folder");
+
+        assert!(is_folder_writable(&dir));
+        // The probe doesn't linger
+        assert_eq!(fs_err::read_dir(&dir).unwrap().count(), 0);
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_folder_isnt_writable() {
+        let dir = test_dir("missing-folder");
+
+        assert!(!is_folder_writable(dir.join("missing")));
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_isnt_a_writable_folder() {
+        let dir = test_dir("file-not-folder");
+        let file_path = dir.join("file");
+        fs_err::write(&file_path, "").unwrap();
+
+        assert!(!is_folder_writable(&file_path));
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    fn relative_time(elapsed_secs: u64) -> String {
+        let now = time::SystemTime::now();
+        format_relative_time_at(now - time::Duration::from_secs(elapsed_secs), now)
+    }
+
+    #[test]
+    fn relative_time_cutoffs() {
+        assert_eq!(relative_time(0), "just now");
+        assert_eq!(relative_time(9), "just now");
+        assert_eq!(relative_time(10), "10 seconds ago");
+
+        assert_eq!(relative_time(59), "59 seconds ago");
+        assert_eq!(relative_time(60), "1 minute ago");
+
+        assert_eq!(relative_time(60 * 60 - 1), "59 minutes ago");
+        assert_eq!(relative_time(60 * 60), "1 hour ago");
+
+        assert_eq!(relative_time(24 * 60 * 60 - 1), "23 hours ago");
+        assert_eq!(relative_time(24 * 60 * 60), "1 day ago");
+        assert_eq!(relative_time(3 * 24 * 60 * 60), "3 days ago");
+    }
+
+    #[test]
+    fn relative_time_in_future_is_just_now() {
+        let now = time::SystemTime::now();
+        let then = now + time::Duration::from_secs(60 * 60);
+
+        assert_eq!(format_relative_time_at(then, now), "just now");
+    }
+
+    #[test]
+    fn device_name_control_chars() {
+        assert_eq!(
+            sanitize_device_name("Pixel\u{7}\u{1b}[31m 8"),
+            "Pixel[31m 8"
+        );
+        // Whitespace is kept apart rather than dropped
+        assert_eq!(sanitize_device_name("Living\nRoom\tTV"), "Living Room TV");
+        assert_eq!(sanitize_device_name("\r\n Phone \u{0}"), "Phone");
+        assert_eq!(sanitize_device_name("\u{0}\u{1f}"), "");
+    }
+
+    #[test]
+    fn device_name_bidi_overrides() {
+        // Would otherwise read as "Phoneexe.jpg"
+        assert_eq!(sanitize_device_name("Phone\u{202E}gpj.exe"), "Phonegpj.exe");
+        assert_eq!(
+            sanitize_device_name("\u{2066}Laptop\u{2069}\u{200F}\u{061C}"),
+            "Laptop"
+        );
+        // Right-to-left text itself is left alone
+        assert_eq!(sanitize_device_name("هاتف"), "هاتف");
+    }
+
+    #[test]
+    fn device_name_long_names() {
+        let name = "a".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS);
+        assert_eq!(sanitize_device_name(&name), name);
+
+        let name = "a".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS + 1);
+        assert_eq!(
+            sanitize_device_name(&name),
+            format!("{}…", "a".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS))
+        );
+
+        // Counted in characters, not bytes
+        let name = "é".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS);
+        assert_eq!(sanitize_device_name(&name), name);
+
+        // No space left dangling before the ellipsis
+        let name = format!("{} b", "a".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS - 1));
+        assert_eq!(
+            sanitize_device_name(&name),
+            format!("{}…", "a".repeat(DEVICE_NAME_DISPLAY_MAX_CHARS - 1))
+        );
+    }
+
+    #[test]
+    fn atomic_write_replaces_contents() {
+        let dir = test_dir("atomic-write");
+        let path = dir.join("state.json");
+
+        write_atomic(&path, "[1, 2, 3]").unwrap();
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), "[1, 2, 3]");
+
+        write_atomic(&path, "[4]").unwrap();
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), "[4]");
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn interrupted_atomic_write_keeps_previous_contents() {
+        let dir = test_dir("atomic-interrupted");
+        let path = dir.join("state.json");
+        write_atomic(&path, "[1, 2, 3]").unwrap();
+
+        // Fails halfway through, as if the disk filled up
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"[4, 5")?;
+            Err(io::Error::other("interrupted"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), "[1, 2, 3]");
+        assert!(!atomic_tmp_path(&path).exists());
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn killed_atomic_write_keeps_previous_contents() {
+        let dir = test_dir("atomic-killed");
+        let path = dir.join("state.json");
+        write_atomic(&path, "[1, 2, 3]").unwrap();
+
+        // Killed before the rename, the half written file is left behind
+        fs_err::write(atomic_tmp_path(&path), "[4, 5").unwrap();
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), "[1, 2, 3]");
+
+        // And doesn't get in the way of the next write
+        write_atomic(&path, "[6]").unwrap();
+        assert_eq!(fs_err::read_to_string(&path).unwrap(), "[6]");
+        assert!(!atomic_tmp_path(&path).exists());
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_json_state_is_default() {
+        assert_eq!(
+            json_or_default::<Vec<u32>>("[1, 2, 3]", "state"),
+            vec![1, 2, 3]
+        );
+        // e.g. after being edited by hand
+        assert_eq!(
+            json_or_default::<Vec<u32>>("[1, 2", "state"),
+            Vec::<u32>::new()
+        );
+        assert_eq!(json_or_default::<Vec<u32>>("", "state"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn size_bytes_are_pluralized() {
+        assert_eq!(format_size_with_separator(0, "."), "0 bytes");
+        assert_eq!(format_size_with_separator(1, "."), "1 byte");
+        assert_eq!(format_size_with_separator(999, "."), "999 bytes");
+    }
+
+    #[test]
+    fn size_units() {
+        assert_eq!(format_size_with_separator(1000, "."), "1.0 kB");
+        assert_eq!(format_size_with_separator(1_500_000, "."), "1.5 MB");
+        assert_eq!(format_size_with_separator(42_300_000_000, "."), "42.3 GB");
+        assert_eq!(format_size_with_separator(2_000_000_000_000, "."), "2.0 TB");
+        assert_eq!(format_size_with_separator(u64::MAX, "."), "18446.7 PB");
+
+        // Rounded up into the next unit
+        assert_eq!(format_size_with_separator(999_960, "."), "1.0 MB");
+    }
+
+    #[test]
+    fn size_locale_separators() {
+        // en_US
+        assert_eq!(format_size_with_separator(1_500_000, "."), "1.5 MB");
+        // de_DE, fr_FR
+        assert_eq!(format_size_with_separator(1_500_000, ","), "1,5 MB");
+        // ar_EG
+        assert_eq!(
+            format_size_with_separator(1_500_000, "\u{066B}"),
+            "1\u{066B}5 MB"
+        );
+
+        assert_eq!(decimal_separator_of("1.5 kB"), Some("."));
+        assert_eq!(decimal_separator_of("1,5 kB"), Some(","));
+        assert_eq!(decimal_separator_of("1,5 ko"), Some(","));
+        assert_eq!(decimal_separator_of("1\u{066B}5 kB"), Some("\u{066B}"));
+        assert_eq!(decimal_separator_of("1500 bytes"), None);
+    }
+}
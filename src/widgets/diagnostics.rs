@@ -0,0 +1,248 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use formatx::formatx;
+use gettextrs::gettext;
+use gtk::glib::{self, clone};
+
+use crate::{
+    config::{PROFILE, VERSION},
+    monitors::{self, NetworkState},
+    window::PacketApplicationWindow,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Ok,
+    /// Not a problem by itself, e.g. discovery only runs while picking recipients
+    Inactive,
+    Failed,
+}
+
+impl CheckStatus {
+    fn from_bool(is_ok: bool) -> Self {
+        if is_ok { Self::Ok } else { Self::Failed }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Inactive => "inactive",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Check {
+    /// Untranslated, for the copied report
+    key: &'static str,
+    title: String,
+    detail: String,
+    status: CheckStatus,
+}
+
+async fn run_checks(win: &PacketApplicationWindow, static_port: Option<i32>) -> Vec<Check> {
+    let imp = win.imp();
+
+    let network = imp.network_state.get();
+    let network_check = Check {
+        key: "network",
+        title: gettext("Network"),
+        detail: match network {
+            NetworkState::NoInterface => gettext("No network interface is up"),
+            NetworkState::Disconnected => gettext("Not connected to a network"),
+            NetworkState::Limited => gettext("Connected, without internet access"),
+            NetworkState::Full => gettext("Connected"),
+        },
+        status: CheckStatus::from_bool(network.is_available()),
+    };
+
+    // The monitor might be in the middle of reconnecting, so ask directly
+    let conn = imp.dbus_system_conn.borrow().clone();
+    let is_bluetooth_powered = match conn {
+        Some(conn) => monitors::is_bluetooth_powered(&conn)
+            .await
+            .inspect_err(|err| tracing::warn!(%err, "Couldn't query the Bluetooth state"))
+            .unwrap_or_default(),
+        None => imp.bluetooth_state.get(),
+    };
+    let bluetooth_check = Check {
+        key: "bluetooth",
+        title: gettext("Bluetooth"),
+        detail: if is_bluetooth_powered {
+            gettext("Turned on")
+        } else {
+            gettext("Turned off or unavailable")
+        },
+        status: CheckStatus::from_bool(is_bluetooth_powered),
+    };
+
+    let is_service_running = imp.rqs.lock().await.is_some();
+    let service_check = match (is_service_running, static_port) {
+        (false, _) => Check {
+            key: "service",
+            title: gettext("Receiving Service"),
+            detail: gettext("Not running"),
+            status: CheckStatus::Failed,
+        },
+        // Something holding the port while the service runs is taken to be us
+        (true, Some(port)) => {
+            let is_port_bound = !port_scanner::local_port_available(port as u16);
+            Check {
+                key: "service",
+                title: gettext("Receiving Service"),
+                detail: if is_port_bound {
+                    formatx!(gettext("Listening on port {}"), port)
+                } else {
+                    formatx!(gettext("Port {} isn't bound"), port)
+                }
+                .unwrap_or_else(|_| "badly formatted locale string".into()),
+                status: CheckStatus::from_bool(is_port_bound),
+            }
+        }
+        (true, None) => Check {
+            key: "service",
+            title: gettext("Receiving Service"),
+            detail: gettext("Running on an automatically assigned port"),
+            status: CheckStatus::Ok,
+        },
+    };
+
+    let is_visible = imp.device_visibility_switch.is_active();
+    let visibility_check = Check {
+        key: "mdns-advertising",
+        title: gettext("Visibility"),
+        detail: if is_visible {
+            gettext("Announced to nearby devices")
+        } else {
+            gettext("Invisible, nearby devices can't find this one")
+        },
+        status: if is_visible {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Inactive
+        },
+    };
+
+    let is_discovery_on = imp.is_mdns_discovery_on.get();
+    let discovery_check = Check {
+        key: "mdns-discovery",
+        title: gettext("Discovery"),
+        detail: if is_discovery_on {
+            gettext("Looking for nearby devices")
+        } else {
+            gettext("Only looks for devices while picking recipients")
+        },
+        status: if is_discovery_on {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Inactive
+        },
+    };
+
+    vec![
+        network_check,
+        bluetooth_check,
+        service_check,
+        visibility_check,
+        discovery_check,
+    ]
+}
+
+/// Meant to be pasted into bug reports, so it's left untranslated.
+fn format_report(checks: &[Check], static_port: Option<i32>) -> String {
+    let mut report = format!("Packet {VERSION} ({PROFILE})\n");
+    if std::env::var("FLATPAK_ID").is_ok_and(|it| !it.is_empty()) {
+        report.push_str("flatpak: yes\n");
+    }
+    match static_port {
+        Some(port) => report.push_str(&format!("static-port: {port}\n")),
+        None => report.push_str("static-port: off\n"),
+    }
+    for check in checks {
+        report.push_str(&format!("{}: {}\n", check.key, check.status.as_str()));
+    }
+
+    report
+}
+
+fn create_check_row(check: &Check) -> adw::ActionRow {
+    let row = adw::ActionRow::builder()
+        .title(&check.title)
+        .subtitle(&check.detail)
+        .build();
+
+    let (icon_name, css_class) = match check.status {
+        CheckStatus::Ok => ("emblem-ok-symbolic", "success"),
+        CheckStatus::Inactive => ("media-playback-pause-symbolic", "dimmed"),
+        CheckStatus::Failed => ("dialog-error-symbolic", "error"),
+    };
+    row.add_suffix(
+        &gtk::Image::builder()
+            .icon_name(icon_name)
+            .css_classes([css_class])
+            .build(),
+    );
+
+    row
+}
+
+/// Shows what's needed for devices to find and reach each other, along with
+/// a way to copy it for bug reports.
+pub fn present_diagnostics_dialog(win: &PacketApplicationWindow) {
+    glib::spawn_future_local(clone!(
+        #[weak]
+        win,
+        async move {
+            let settings = &win.imp().settings;
+            let static_port = settings
+                .boolean("enable-static-port")
+                .then(|| settings.int("static-port-number"));
+            let checks = run_checks(&win, static_port).await;
+            let report = format_report(&checks, static_port);
+            tracing::debug!(%report, "Ran diagnostics");
+
+            let group = adw::PreferencesGroup::builder()
+                .description(&gettext(
+                    "If devices still can't find each other, make sure a firewall \
+                    isn't blocking mDNS (UDP port 5353) or the port Packet listens on.",
+                ))
+                .build();
+            for check in &checks {
+                group.add(&create_check_row(check));
+            }
+
+            let copy_group = adw::PreferencesGroup::new();
+            let copy_row = adw::ButtonRow::builder()
+                .title(&gettext("Copy Diagnostics"))
+                .start_icon_name("edit-copy-symbolic")
+                .build();
+            copy_group.add(&copy_row);
+
+            let page = adw::PreferencesPage::new();
+            page.add(&group);
+            page.add(&copy_group);
+
+            // The window's toasts would be covered by the dialog
+            let toast_overlay = adw::ToastOverlay::builder().child(&page).build();
+            copy_row.connect_activated(clone!(
+                #[weak]
+                toast_overlay,
+                move |row| {
+                    row.clipboard().set_text(&report);
+                    toast_overlay.add_toast(adw::Toast::new(&gettext("Copied diagnostics")));
+                }
+            ));
+
+            let toolbar_view = adw::ToolbarView::builder().content(&toast_overlay).build();
+            toolbar_view.add_top_bar(&adw::HeaderBar::new());
+
+            let dialog = adw::Dialog::builder()
+                .title(&gettext("Diagnostics"))
+                .content_width(360)
+                .child(&toolbar_view)
+                .build();
+            dialog.present(Some(&win));
+        }
+    ));
+}
@@ -3,11 +3,12 @@ use adw::subclass::prelude::*;
 use formatx::formatx;
 use gettextrs::{gettext, ngettext};
 use gtk::{
+    gdk,
     gio::{self, FileQueryInfoFlags},
     glib::{self, clone},
 };
 
-use crate::window::PacketApplicationWindow;
+use crate::{utils, window::PacketApplicationWindow};
 
 // These are the icons that Files/nautilus uses
 // https://gitlab.gnome.org/GNOME/adwaita-icon-theme/-/tree/master/Adwaita/scalable?ref_type=heads
@@ -77,8 +78,6 @@ pub fn create_file_card(
     model: &gio::ListStore,
     model_item: &gio::File,
 ) -> adw::Bin {
-    let imp = win.imp();
-
     let root_bin = adw::Bin::new();
     let _box = gtk::Box::builder().build();
     let root_box = gtk::Box::builder()
@@ -90,6 +89,12 @@ pub fn create_file_card(
         .build();
     root_bin.set_child(Some(&_box));
     _box.append(&root_box);
+    let drag_handle = gtk::Image::builder()
+        .icon_name("list-drag-handle-symbolic")
+        .tooltip_text(&gettext("Drag to reorder"))
+        .css_classes(["dimmed"])
+        .build();
+    root_box.append(&drag_handle);
     let file_avatar = gtk::Image::builder()
         .icon_name(
             &get_mimetype_icon_name(&model_item, false).unwrap_or("application-x-generic".into()),
@@ -111,7 +116,43 @@ pub fn create_file_card(
         .wrap(true)
         .wrap_mode(gtk::pango::WrapMode::Char)
         .build();
-    root_box.append(&filename_label);
+    let size_label = gtk::Label::builder()
+        .xalign(0.)
+        .visible(false)
+        .css_classes(["dimmed", "caption"])
+        .build();
+    let label_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .valign(gtk::Align::Center)
+        .hexpand(true)
+        .spacing(2)
+        .build();
+    label_box.append(&filename_label);
+    label_box.append(&size_label);
+    root_box.append(&label_box);
+
+    glib::spawn_future_local(clone!(
+        #[weak]
+        model_item,
+        #[weak]
+        size_label,
+        async move {
+            let Ok(info) = model_item
+                .query_info_future(
+                    gio::FILE_ATTRIBUTE_STANDARD_SIZE,
+                    FileQueryInfoFlags::NONE,
+                    glib::Priority::DEFAULT,
+                )
+                .await
+                .inspect_err(|err| tracing::debug!(%err, "Couldn't get the file size"))
+            else {
+                return;
+            };
+
+            size_label.set_label(&utils::format_size_localized(info.size() as u64));
+            size_label.set_visible(true);
+        }
+    ));
 
     let remove_file_button = gtk::Button::builder()
         .valign(gtk::Align::Center)
@@ -124,29 +165,278 @@ pub fn create_file_card(
 
     remove_file_button.connect_clicked(clone!(
         #[weak]
-        imp,
+        win,
         #[weak]
         model,
         #[weak]
         model_item,
         move |_| {
-            if let Some(pos) = model.find(&model_item) {
-                model.remove(pos);
-            }
+            remove_file(&win, &model, &model_item);
+        }
+    ));
 
-            imp.manage_files_header.set_title(
-                &formatx!(
-                    ngettext("{} File", "{} Files", model.n_items()),
-                    model.n_items() as usize
-                )
-                .unwrap_or_else(|_| "badly formatted locale string".into()),
-            );
+    setup_file_card_context_menu(win, &root_bin, model, model_item);
+
+    root_bin
+}
+
+/// Removes the file from the selection, leaving the page if it was the last one.
+pub fn remove_file(win: &PacketApplicationWindow, model: &gio::ListStore, model_item: &gio::File) {
+    let imp = win.imp();
+
+    if let Some(pos) = model.find(model_item) {
+        model.remove(pos);
+    }
+
+    imp.manage_files_header.set_title(
+        &formatx!(
+            ngettext("{} File", "{} Files", model.n_items()),
+            model.n_items() as usize
+        )
+        .unwrap_or_else(|_| "badly formatted locale string".into()),
+    );
+
+    if model.n_items() == 0 {
+        // Removing all of the files is taken as not wanting to resend them either
+        imp.previous_files.borrow_mut().clear();
+        imp.main_resend_files_button.set_visible(false);
+
+        imp.main_nav_view.pop();
+    }
+}
+
+/// Opened with a right click or long press, or with the Menu key and
+/// Shift+F10 while something in the card has focus.
+fn setup_file_card_context_menu(
+    win: &PacketApplicationWindow,
+    root_bin: &adw::Bin,
+    model: &gio::ListStore,
+    model_item: &gio::File,
+) {
+    let actions = gio::SimpleActionGroup::new();
+    actions.add_action_entries([
+        gio::ActionEntry::builder("remove")
+            .activate(clone!(
+                #[weak]
+                win,
+                #[weak]
+                model,
+                #[weak]
+                model_item,
+                move |_: &gio::SimpleActionGroup, _, _| {
+                    remove_file(&win, &model, &model_item);
+                }
+            ))
+            .build(),
+        gio::ActionEntry::builder("open-folder")
+            .activate(clone!(
+                #[weak]
+                win,
+                #[weak]
+                model_item,
+                move |_: &gio::SimpleActionGroup, _, _| {
+                    gtk::FileLauncher::new(Some(&model_item)).open_containing_folder(
+                        Some(&win),
+                        None::<&gio::Cancellable>,
+                        |_| {},
+                    );
+                }
+            ))
+            .build(),
+        gio::ActionEntry::builder("copy-path")
+            .activate(clone!(
+                #[weak]
+                win,
+                #[weak]
+                model_item,
+                move |_: &gio::SimpleActionGroup, _, _| {
+                    let path = model_item
+                        .path()
+                        .map(|it| it.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| model_item.parse_name().into());
+                    win.clipboard().set_text(&path);
+                }
+            ))
+            .build(),
+    ]);
+    root_bin.insert_action_group("file-card", Some(&actions));
+
+    let menu = gio::Menu::new();
+    menu.append(
+        Some(&gettext("Open Containing Folder")),
+        Some("file-card.open-folder"),
+    );
+    menu.append(Some(&gettext("Copy Path")), Some("file-card.copy-path"));
+    let remove_section = gio::Menu::new();
+    remove_section.append(Some(&gettext("Remove")), Some("file-card.remove"));
+    menu.append_section(None, &remove_section);
+
+    let popover = gtk::PopoverMenu::builder()
+        .menu_model(&menu)
+        .has_arrow(false)
+        .halign(gtk::Align::Start)
+        .build();
+    popover.set_parent(root_bin);
+    root_bin.connect_destroy(clone!(
+        #[strong]
+        popover,
+        move |_| {
+            popover.unparent();
+        }
+    ));
+
+    let popup_at = clone!(
+        #[weak]
+        popover,
+        move |x: f64, y: f64| {
+            popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popover.popup();
+        }
+    );
+
+    let right_click = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_SECONDARY)
+        .build();
+    right_click.connect_pressed(clone!(
+        #[strong]
+        popup_at,
+        move |gesture, _, x, y| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            popup_at(x, y);
+        }
+    ));
+    root_bin.add_controller(right_click);
+
+    let long_press = gtk::GestureLongPress::builder().touch_only(true).build();
+    long_press.connect_pressed(move |gesture, x, y| {
+        gesture.set_state(gtk::EventSequenceState::Claimed);
+        popup_at(x, y);
+    });
+    root_bin.add_controller(long_press);
 
-            if model.n_items() == 0 {
-                imp.main_nav_view.pop();
+    let shortcut_controller = gtk::ShortcutController::new();
+    shortcut_controller.add_shortcut(gtk::Shortcut::new(
+        gtk::ShortcutTrigger::parse_string("Menu|<Shift>F10"),
+        Some(gtk::CallbackAction::new(clone!(
+            #[weak]
+            popover,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, _| {
+                popover.set_pointing_to(None::<&gdk::Rectangle>);
+                popover.popup();
+                glib::Propagation::Stop
             }
+        ))),
+    ));
+    root_bin.add_controller(shortcut_controller);
+}
+
+/// Moves `item` to `to_pos` in the file list, returning whether it moved.
+fn move_file_item(model: &gio::ListStore, item: &gio::File, to_pos: u32) -> bool {
+    let Some(from_pos) = model.find(item) else {
+        return false;
+    };
+    if from_pos == to_pos || to_pos >= model.n_items() {
+        return false;
+    }
+
+    tracing::debug!(from_pos, to_pos, "Reordering files");
+    model.remove(from_pos);
+    model.insert(to_pos, item);
+
+    true
+}
+
+/// Lets the file rows be dragged onto one another, or moved with
+/// <kbd>Alt</kbd>+<kbd>Up</kbd>/<kbd>Down</kbd>, to change the order the files
+/// are sent in.
+pub fn setup_file_row_reordering(
+    row: &gtk::ListBoxRow,
+    model: &gio::ListStore,
+    model_item: &gio::File,
+) {
+    let drag_source = gtk::DragSource::builder()
+        .actions(gdk::DragAction::MOVE)
+        .content(&gdk::ContentProvider::for_value(&model_item.to_value()))
+        .build();
+    drag_source.connect_drag_begin(clone!(
+        #[weak]
+        row,
+        move |drag_source, _| {
+            drag_source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&row))), 0, 0);
         }
     ));
+    row.add_controller(drag_source);
 
-    root_bin
+    // Only accepts the rows themselves, files dropped from outside are handled
+    // by the page
+    let drop_target = gtk::DropTarget::new(gio::File::static_type(), gdk::DragAction::MOVE);
+    drop_target.connect_accept(|_, drop| {
+        // Drags started from another app, e.g. a file manager, have no local
+        // `gdk::Drag`, so they fall through to the page's drop target
+        drop.drag().is_some() && drop.formats().contain_gtype(gio::File::static_type())
+    });
+    drop_target.connect_drop(clone!(
+        #[weak]
+        model,
+        #[weak]
+        model_item,
+        #[upgrade_or]
+        false,
+        move |_, value, _, _| {
+            let Ok(dragged_item) = value.get::<gio::File>() else {
+                return false;
+            };
+            // Not an item from this list
+            let Some(to_pos) = model.find(&model_item) else {
+                return false;
+            };
+
+            move_file_item(&model, &dragged_item, to_pos)
+        }
+    ));
+    row.add_controller(drop_target);
+
+    let move_by = clone!(
+        #[weak]
+        row,
+        #[weak]
+        model,
+        #[weak]
+        model_item,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |offset: i32| {
+            let Some(from_pos) = model.find(&model_item) else {
+                return glib::Propagation::Proceed;
+            };
+            let Some(to_pos) = from_pos.checked_add_signed(offset) else {
+                return glib::Propagation::Stop;
+            };
+            // The row gets rebuilt when the item moves, so hold onto the list
+            // to hand the focus over to the new row
+            let list_box = row.parent().and_downcast::<gtk::ListBox>();
+            if move_file_item(&model, &model_item, to_pos) {
+                if let Some(new_row) = list_box.and_then(|it| it.row_at_index(to_pos as i32)) {
+                    new_row.grab_focus();
+                }
+            }
+
+            glib::Propagation::Stop
+        }
+    );
+
+    let shortcut_controller = gtk::ShortcutController::new();
+    for (trigger, offset) in [("<Alt>Up", -1), ("<Alt>Down", 1)] {
+        shortcut_controller.add_shortcut(gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string(trigger),
+            Some(gtk::CallbackAction::new(clone!(
+                #[strong]
+                move_by,
+                move |_, _| move_by(offset)
+            ))),
+        ));
+    }
+    row.add_controller(shortcut_controller);
 }
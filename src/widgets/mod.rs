@@ -1,7 +1,11 @@
+mod diagnostics;
 mod file_card;
 mod receive_transfer;
 mod recipient_card;
+mod wifi_composer;
 
+pub use diagnostics::*;
 pub use file_card::*;
 pub use receive_transfer::*;
 pub use recipient_card::*;
+pub use wifi_composer::*;
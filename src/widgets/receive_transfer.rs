@@ -1,4 +1,10 @@
-use std::{cell::Cell, rc::Rc, time::Duration};
+use std::{
+    cell::{Cell, RefCell},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
@@ -6,19 +12,27 @@ use ashpd::desktop::notification::{DisplayHint, Notification, Priority};
 use formatx::formatx;
 use gettextrs::{gettext, ngettext};
 use gtk::{
-    gio,
+    gdk, gio,
     glib::{self, clone},
 };
+use regex::Regex;
 use rqs_lib::hdl::TextPayloadType;
 use tokio_util::sync::CancellationToken;
 
 use crate::{
     ext::MessageExt,
+    monitors,
     objects::{self, UserAction},
-    utils::{remove_notification, spawn_notification},
+    tokio_runtime,
+    utils::{self, remove_notification, spawn_notification},
+    widgets::{create_pincode_copy_button, get_mimetype_icon_name},
     window::PacketApplicationWindow,
 };
 
+/// How long to wait for the sender to start sending after accepting, before
+/// assuming it dropped off the network.
+const RECEIVE_START_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub fn display_text_type(value: &TextPayloadType) -> String {
     match value {
         TextPayloadType::Url => gettext("Link"),
@@ -27,6 +41,33 @@ pub fn display_text_type(value: &TextPayloadType) -> String {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NotificationKind {
+    /// Requests and ongoing transfers
+    Progress,
+    /// Outcomes of the transfers
+    Completion,
+}
+
+/// Only gates the system notifications, toasts and dialogs are always shown.
+fn should_show_notification(win: &PacketApplicationWindow, kind: NotificationKind) -> bool {
+    match win.imp().settings.string("notification-level").as_str() {
+        "none" => false,
+        "completion-only" => kind == NotificationKind::Completion,
+        _ => true,
+    }
+}
+
+pub fn display_wifi_security_type(security_type: &str) -> String {
+    // Based on the variant names of the protocol's `WifiCredentialsMetadata.SecurityType`
+    match security_type {
+        "Open" => gettext("None"),
+        "WpaPsk" => "WPA/WPA2".into(),
+        "Wep" => "WEP".into(),
+        _ => gettext("Unknown"),
+    }
+}
+
 // So, Quick Share wraps the string in `""\n` sometimes, it seem to differ based
 // on where you're copying the text from. For e.g. sharing from the Github app doesn't
 // wrap the string in quote, but it does when shared from Chrome.
@@ -48,6 +89,586 @@ fn clean_preview_text_payload(s: &str) -> &str {
         .trim_matches(|c| c == '"' || c == '\n')
 }
 
+/// Unique http(s) URLs found in the text, in the order they appear.
+fn detect_urls(s: &str) -> Vec<&str> {
+    static URL_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap());
+
+    let mut urls = vec![];
+    for url in URL_REGEX.find_iter(s).map(|it| {
+        // Punctuation right after a link is more likely to belong to the sentence
+        it.as_str()
+            .trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | ')' | ']'))
+    }) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+fn create_links_listbox(win: &PacketApplicationWindow, urls: &[&str]) -> gtk::ListBox {
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .valign(gtk::Align::Start)
+        .css_classes(["boxed-list"])
+        .build();
+
+    for url in urls {
+        let row = adw::ActionRow::builder()
+            .title(*url)
+            .title_lines(1)
+            .use_markup(false)
+            .activatable(true)
+            .tooltip_text(*url)
+            .build();
+        row.add_suffix(&gtk::Image::from_icon_name("adw-external-link-symbolic"));
+
+        let url = url.to_string();
+        row.connect_activated(clone!(
+            #[weak]
+            win,
+            move |_| {
+                gtk::UriLauncher::new(&url).launch(
+                    win.root().and_downcast_ref::<adw::ApplicationWindow>(),
+                    None::<gio::Cancellable>.as_ref(),
+                    |_err| {},
+                );
+            }
+        ));
+        listbox.append(&row);
+    }
+
+    listbox
+}
+
+/// Resolves where the received files should've ended up, based on the names
+/// sent by the peer.
+///
+/// rqs_lib doesn't report the final destination paths, so files that got
+/// renamed by it (e.g. to avoid overwriting an existing file) won't be found.
+fn received_file_paths(download_folder: &Path, file_names: &[String]) -> Vec<PathBuf> {
+    file_names
+        .iter()
+        // Don't trust the names to not contain path components
+        .filter_map(|it| Path::new(it).file_name())
+        .map(|it| download_folder.join(it))
+        .filter(|it| it.is_file())
+        .collect()
+}
+
+/// The subfolder of `folder` named after today's date, e.g. `2025-01-31`,
+/// created if it doesn't exist yet.
+fn dated_download_folder(folder: &Path) -> anyhow::Result<PathBuf> {
+    let today = glib::DateTime::now_local()?.format("%Y-%m-%d")?;
+    let dated_folder = folder.join(today.as_str());
+    fs_err::create_dir_all(&dated_folder)?;
+
+    Ok(dated_folder)
+}
+
+/// Resolves the folder to receive into, with the dated subfolder if
+/// `is_organized_by_date`, or hands it back as the error if files can't be
+/// saved to it. Blocks on the file system.
+fn prepare_download_folder(
+    folder: PathBuf,
+    is_organized_by_date: bool,
+) -> Result<PathBuf, PathBuf> {
+    let folder = if is_organized_by_date {
+        match dated_download_folder(&folder) {
+            Ok(dated_folder) => dated_folder,
+            Err(err) => {
+                tracing::warn!(%err, ?folder, "Couldn't create the dated folder");
+                folder
+            }
+        }
+    } else {
+        folder
+    };
+
+    if utils::is_folder_writable(&folder) {
+        Ok(folder)
+    } else {
+        Err(folder)
+    }
+}
+
+/// Lets the user know the transfer failed, in the notification if they're
+/// shown and in a toast.
+fn notify_receive_failure(
+    win: &PacketApplicationWindow,
+    notification_id: String,
+    device_name: &str,
+    body: &str,
+) {
+    if should_show_notification(win, NotificationKind::Completion) {
+        spawn_notification(
+            notification_id,
+            Notification::new(device_name)
+                .body(body)
+                .priority(Priority::High)
+                .default_action(None),
+        );
+    }
+    win.imp().toast_overlay.add_toast(
+        adw::Toast::builder()
+            .title(body)
+            .priority(adw::ToastPriority::High)
+            .build(),
+    );
+}
+
+/// Cancels the transfer once `trigger` resolves, telling the user why with
+/// `body`, unless the returned token gets cancelled first.
+fn cancel_receive_on(
+    win: &PacketApplicationWindow,
+    receive_state: &objects::ReceiveTransferState,
+    notification_id: String,
+    trigger: impl Future<Output = ()> + 'static,
+    body: String,
+) -> CancellationToken {
+    let ctk = CancellationToken::new();
+
+    glib::spawn_future_local(clone!(
+        #[weak]
+        win,
+        #[weak]
+        receive_state,
+        #[strong]
+        ctk,
+        async move {
+            tokio::select! {
+                _ = trigger => {}
+                _ = ctk.cancelled() => {
+                    return;
+                }
+            };
+
+            tracing::warn!(reason = %body, "Cancelling receive");
+            receive_state.set_user_action(Some(UserAction::TransferCancel));
+
+            let Some(event) = receive_state.event() else {
+                return;
+            };
+            notify_receive_failure(&win, notification_id, &event.display_device_name(), &body);
+        }
+    ));
+
+    ctk
+}
+
+/// rqs_lib has no way to pick the name files are saved under, so a file
+/// renamed before accepting is renamed on disk once it's received instead.
+///
+/// An existing file with the new name isn't overwritten, a number is appended
+/// to the name instead, e.g. `photo (1).jpg`.
+///
+/// Returns `None` without renaming anything if the file at `file_path` was last
+/// modified before `accepted_at`. It's then one of the user's own files, with
+/// rqs_lib having saved the received one under another name.
+fn rename_received_file(
+    file_path: &Path,
+    new_name: &str,
+    accepted_at: SystemTime,
+) -> anyhow::Result<Option<PathBuf>> {
+    // Some filesystems, e.g. FAT, only keep the time to within 2 seconds
+    const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+    let folder = file_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Received file has no parent folder"))?;
+
+    let modified = fs_err::metadata(file_path)?.modified()?;
+    if modified + MTIME_SLACK < accepted_at {
+        return Ok(None);
+    }
+
+    let new_name = Path::new(new_name);
+    let stem = new_name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = new_name
+        .extension()
+        .map(|it| format!(".{}", it.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut new_path = folder.join(new_name);
+    let mut n = 1;
+    while new_path.exists() {
+        new_path = folder.join(format!("{stem} ({n}){extension}"));
+        n += 1;
+    }
+
+    fs_err::rename(file_path, &new_path)?;
+
+    Ok(Some(new_path))
+}
+
+/// Whether the received files take up as many bytes on disk as the sender said
+/// they would, anything else means they likely got truncated along the way.
+///
+/// Returns `None` when not every file could be found, e.g. after being renamed
+/// on a name conflict, since there's nothing reliable to compare against then.
+///
+/// Blocking.
+fn is_received_size_matching(
+    file_paths: &[PathBuf],
+    expected_file_count: usize,
+    expected_total_bytes: u64,
+) -> Option<bool> {
+    if file_paths.len() != expected_file_count {
+        return None;
+    }
+
+    let mut total_bytes = 0;
+    for path in file_paths {
+        total_bytes += fs_err::metadata(path)
+            .inspect_err(|err| tracing::debug!(%err, "Skipping the received size check"))
+            .ok()?
+            .len();
+    }
+
+    Some(total_bytes == expected_total_bytes)
+}
+
+fn create_wifi_credentials_listbox(
+    clipboard: &gdk::Clipboard,
+    wifi_data: &objects::WifiData,
+) -> gtk::ListBox {
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .valign(gtk::Align::Start)
+        .css_classes(["boxed-list"])
+        .build();
+
+    fn create_copyable_row(
+        clipboard: &gdk::Clipboard,
+        title: &str,
+        value: &str,
+        copy_tooltip: &str,
+    ) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(title)
+            .subtitle(value)
+            .subtitle_selectable(true)
+            .use_markup(false)
+            .css_classes(["property"])
+            .build();
+
+        let copy_button = gtk::Button::builder()
+            .valign(gtk::Align::Center)
+            .icon_name("edit-copy-symbolic")
+            .tooltip_text(copy_tooltip)
+            .css_classes(["flat"])
+            .build();
+        let value = value.to_string();
+        copy_button.connect_clicked(clone!(
+            #[strong]
+            clipboard,
+            move |_| {
+                clipboard.set_text(&value);
+            }
+        ));
+        row.add_suffix(&copy_button);
+
+        row
+    }
+
+    listbox.append(&create_copyable_row(
+        clipboard,
+        &gettext("Network Name"),
+        &wifi_data.ssid,
+        &gettext("Copy SSID"),
+    ));
+    listbox.append(&create_copyable_row(
+        clipboard,
+        &gettext("Password"),
+        &wifi_data.password,
+        &gettext("Copy Password"),
+    ));
+    listbox.append(
+        &adw::ActionRow::builder()
+            .title(gettext("Security"))
+            .subtitle(display_wifi_security_type(&wifi_data.security_type))
+            .css_classes(["property"])
+            .build(),
+    );
+
+    listbox
+}
+
+fn present_received_files_dialog(win: &PacketApplicationWindow, file_paths: &[PathBuf]) {
+    let dialog = adw::Dialog::builder()
+        .content_width(400)
+        .follows_content_size(true)
+        .title(gettext("Received Files"))
+        .build();
+
+    let toolbar_view = adw::ToolbarView::builder()
+        .top_bar_style(adw::ToolbarStyle::Flat)
+        .build();
+    dialog.set_child(Some(&toolbar_view));
+
+    let header_bar = adw::HeaderBar::builder().build();
+    toolbar_view.add_top_bar(&header_bar);
+
+    let open_folder_button = gtk::Button::builder()
+        .valign(gtk::Align::Center)
+        .icon_name("folder-open-symbolic")
+        .tooltip_text(&gettext("Open Folder"))
+        .action_name("win.received-files")
+        .css_classes(["circular", "flat"])
+        .build();
+    header_bar.pack_start(&open_folder_button);
+
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(360)
+        .build();
+    toolbar_view.set_content(Some(&scrolled_window));
+
+    let root_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .margin_top(6)
+        .margin_bottom(18)
+        .margin_start(18)
+        .margin_end(18)
+        .spacing(12)
+        .build();
+    scrolled_window.set_child(Some(&root_box));
+
+    let hint_label = gtk::Label::builder()
+        .label(&gettext("Drag files to another app, or click to open them"))
+        .wrap(true)
+        .css_classes(["dimmed"])
+        .build();
+    root_box.append(&hint_label);
+
+    let files_listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    root_box.append(&files_listbox);
+
+    for file_path in file_paths {
+        let file = gio::File::for_path(file_path);
+
+        let file_icon = gtk::Image::builder()
+            .icon_name(
+                get_mimetype_icon_name(&file, false).unwrap_or("application-x-generic".into()),
+            )
+            .pixel_size(32)
+            .build();
+        let row = adw::ActionRow::builder()
+            .title(file_path.file_name().unwrap_or_default().to_string_lossy())
+            .use_markup(false)
+            .activatable(true)
+            .build();
+        row.add_prefix(&file_icon);
+
+        let drag_source = gtk::DragSource::builder()
+            .actions(gdk::DragAction::COPY)
+            .build();
+        drag_source.set_content(Some(&gdk::ContentProvider::for_value(
+            &gdk::FileList::from_array(&[file.clone()]).to_value(),
+        )));
+        row.add_controller(drag_source);
+
+        row.connect_activated(clone!(
+            #[weak]
+            win,
+            move |_| {
+                gtk::FileLauncher::new(Some(&file)).launch(
+                    Some(&win),
+                    None::<&gio::Cancellable>,
+                    move |_| {},
+                );
+            }
+        ));
+
+        files_listbox.append(&row);
+    }
+
+    dialog.present(Some(win));
+}
+
+fn is_image_file(file_path: &Path) -> bool {
+    get_mimetype_icon_name(&gio::File::for_path(file_path), false).as_deref()
+        == Some("image-x-generic")
+}
+
+/// A thumbnail of the first image among the received files, otherwise the
+/// icon of the kind of files received if they're all of one kind.
+fn received_files_notification_icon(file_paths: &[PathBuf]) -> Option<ashpd::desktop::Icon> {
+    // Scaled down so that the whole picture isn't sent over to the portal
+    const THUMBNAIL_SIZE: i32 = 256;
+
+    let thumbnail = file_paths
+        .iter()
+        .filter(|it| is_image_file(it))
+        .find_map(|it| {
+            gtk::gdk_pixbuf::Pixbuf::from_file_at_scale(it, THUMBNAIL_SIZE, THUMBNAIL_SIZE, true)
+                .and_then(|pixbuf| pixbuf.save_to_bufferv("png", &[]))
+                .inspect_err(|err| {
+                    tracing::debug!(file_path = ?it, %err, "Couldn't make a notification thumbnail")
+                })
+                .ok()
+        });
+    if let Some(thumbnail) = thumbnail {
+        return Some(ashpd::desktop::Icon::Bytes(thumbnail));
+    }
+
+    let mut icon_names = file_paths.iter().map(|it| {
+        get_mimetype_icon_name(&gio::File::for_path(it), false)
+            .unwrap_or("application-x-generic".into())
+    });
+    let icon_name = icon_names.next()?;
+    icon_names
+        .all(|it| it == icon_name)
+        .then(|| ashpd::desktop::Icon::with_names([icon_name]))
+}
+
+fn present_received_image_dialog(win: &PacketApplicationWindow, file_path: &Path) {
+    let file = gio::File::for_path(file_path);
+    let file_name = file_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    let dialog = adw::Dialog::builder()
+        .content_width(480)
+        .content_height(480)
+        .title(&file_name)
+        .build();
+
+    let toolbar_view = adw::ToolbarView::builder()
+        .top_bar_style(adw::ToolbarStyle::Flat)
+        .build();
+    dialog.set_child(Some(&toolbar_view));
+
+    let header_bar = adw::HeaderBar::builder().build();
+    toolbar_view.add_top_bar(&header_bar);
+
+    let open_button = gtk::Button::builder()
+        .valign(gtk::Align::Center)
+        .icon_name("external-link-symbolic")
+        .tooltip_text(&gettext("Open"))
+        .css_classes(["circular", "flat"])
+        .build();
+    let save_as_button = gtk::Button::builder()
+        .valign(gtk::Align::Center)
+        .icon_name("document-save-as-symbolic")
+        .tooltip_text(&gettext("Save As"))
+        .css_classes(["circular", "flat"])
+        .build();
+    header_bar.pack_start(&open_button);
+    header_bar.pack_start(&save_as_button);
+
+    let picture = gtk::Picture::builder()
+        .file(&file)
+        .content_fit(gtk::ContentFit::Contain)
+        .can_shrink(true)
+        .margin_top(6)
+        .margin_bottom(18)
+        .margin_start(18)
+        .margin_end(18)
+        .build();
+    toolbar_view.set_content(Some(&picture));
+
+    open_button.connect_clicked(clone!(
+        #[weak]
+        win,
+        #[strong]
+        file,
+        move |_| {
+            gtk::FileLauncher::new(Some(&file)).launch(
+                Some(&win),
+                None::<&gio::Cancellable>,
+                move |_| {},
+            );
+        }
+    ));
+
+    let src_path = file_path.to_path_buf();
+    save_as_button.connect_clicked(clone!(
+        #[weak]
+        win,
+        move |_| {
+            let src_path = src_path.clone();
+            let file_name = file_name.clone();
+            glib::spawn_future_local(clone!(
+                #[weak]
+                win,
+                async move {
+                    let Ok(dest_file) = gtk::FileDialog::builder()
+                        .initial_name(file_name)
+                        .build()
+                        .save_future(Some(&win))
+                        .await
+                    else {
+                        return;
+                    };
+                    let Some(dest_path) = dest_file.path() else {
+                        return;
+                    };
+
+                    let is_saved = tokio_runtime()
+                        .spawn_blocking(move || fs_err::copy(src_path, dest_path))
+                        .await
+                        .map_err(|err| anyhow::anyhow!(err))
+                        .and_then(|it| Ok(it?))
+                        .inspect_err(|err| tracing::error!("{err:#}"))
+                        .is_ok();
+                    if !is_saved {
+                        win.imp()
+                            .toast_overlay
+                            .add_toast(adw::Toast::new(&gettext("Couldn't save the image")));
+                    }
+                }
+            ));
+        }
+    ));
+
+    dialog.present(Some(win));
+}
+
+fn present_unwritable_folder_dialog(win: &PacketApplicationWindow, folder: &Path) {
+    let dialog = adw::AlertDialog::builder()
+        .heading(&gettext("Can't Save Files"))
+        .body(
+            &formatx!(
+                gettext(
+                    "The transfer was declined since files can't be saved to {}. \
+                    Pick a different downloads folder to receive files."
+                ),
+                utils::strip_user_home_prefix(folder).display()
+            )
+            .unwrap_or_else(|_| "badly formatted locale string".into()),
+        )
+        .default_response("pick")
+        .close_response("close")
+        .build();
+
+    dialog.add_responses(&[
+        ("close", &gettext("Close")),
+        ("pick", &gettext("Pick Folder")),
+    ]);
+    dialog.set_response_appearance("pick", adw::ResponseAppearance::Suggested);
+    dialog.connect_response(
+        Some("pick"),
+        clone!(
+            #[weak]
+            win,
+            move |_, _| {
+                _ = WidgetExt::activate_action(&win, "win.pick-download-folder", None);
+            }
+        ),
+    );
+
+    dialog.present(Some(&win));
+}
+
 // Rewriting receive UI for the 4rd time ;(
 // Using a chain of AlertDialog this time
 pub fn present_receive_transfer_ui(
@@ -62,6 +683,22 @@ pub fn present_receive_transfer_ui(
     let init_id = event.id.clone();
     let win = win.clone();
 
+    // Set only while rqs is pointed at a per-device or dated folder for this transfer
+    let download_folder_override: Rc<RefCell<Option<PathBuf>>> = Default::default();
+    // Armed on accept, until the sender starts sending
+    let receive_start_timeout_ctk: Rc<RefCell<Option<CancellationToken>>> = Default::default();
+    // Armed on accept, until the transfer settles
+    let connection_loss_ctk: Rc<RefCell<Option<CancellationToken>>> = Default::default();
+    // Name a single incoming file was renamed to in the consent dialog
+    let rename_to: Rc<RefCell<Option<String>>> = Default::default();
+    // When the transfer was accepted, files on disk from before that aren't ours
+    let accepted_at: Rc<Cell<Option<SystemTime>>> = Default::default();
+
+    // Asks for consent in a toast and shows the progress in the bottom bar
+    // instead, without blocking the window
+    let is_toast_ui = win.imp().settings.string("receive-ui-style") == "toast";
+    let consent_toast: Rc<RefCell<Option<adw::Toast>>> = Default::default();
+
     // Progress dialog
     let is_user_cancelled = Rc::new(Cell::new(false));
     let progress_dialog = adw::AlertDialog::builder()
@@ -98,13 +735,23 @@ pub fn present_receive_transfer_ui(
         .build();
     progress_stack.add_named(&progress_files_box, Some("progress_files"));
 
-    let device_name = event.device_name();
+    let device_name = event.display_device_name();
     let device_name_box = create_device_name_box(&device_name);
     device_name_box.set_margin_bottom(4);
     progress_files_box.append(&device_name_box);
 
-    let progress_bar = gtk::ProgressBar::new();
-    progress_files_box.append(&progress_bar);
+    let progress_box = gtk::Box::builder().spacing(8).build();
+    progress_files_box.append(&progress_box);
+    let progress_bar = gtk::ProgressBar::builder()
+        .hexpand(true)
+        .valign(gtk::Align::Center)
+        .build();
+    progress_box.append(&progress_bar);
+    let percentage_label = gtk::Label::builder()
+        .visible(false)
+        .css_classes(["caption", "numeric"])
+        .build();
+    progress_box.append(&percentage_label);
     let eta_label = gtk::Label::builder()
         .halign(gtk::Align::Center)
         .wrap(true)
@@ -112,6 +759,22 @@ pub fn present_receive_transfer_ui(
         .build();
     progress_files_box.append(&eta_label);
 
+    // rqs_lib only reports aggregate bytes, so there's no per-file progress to
+    // show here. Listing the files being received is the next best thing for
+    // large batches.
+    let progress_files_listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    let progress_files_scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(240)
+        .visible(false)
+        .child(&progress_files_listbox)
+        .build();
+    progress_files_box.append(&progress_files_scrolled_window);
+
     let progress_text_box = gtk::Box::builder()
         .orientation(gtk::Orientation::Vertical)
         .margin_start(24)
@@ -122,7 +785,28 @@ pub fn present_receive_transfer_ui(
     let device_name_box = create_device_name_box(&device_name);
     device_name_box.set_margin_bottom(4);
     progress_text_box.append(&device_name_box);
-    progress_text_box.append(&adw::Spinner::new());
+    // A spinning indicator is just motion without any progress to convey, so
+    // it gives way to a static label with animations turned off
+    let progress_spinner = adw::Spinner::new();
+    progress_text_box.append(&progress_spinner);
+    let progress_static_label = gtk::Label::builder()
+        .label(&gettext("Receiving…"))
+        .css_classes(["dimmed"])
+        .build();
+    progress_text_box.append(&progress_static_label);
+    if let Some(gtk_settings) = gtk::Settings::default() {
+        gtk_settings
+            .bind_property("gtk-enable-animations", &progress_spinner, "visible")
+            .sync_create()
+            .build();
+        gtk_settings
+            .bind_property("gtk-enable-animations", &progress_static_label, "visible")
+            .invert_boolean()
+            .sync_create()
+            .build();
+    } else {
+        progress_static_label.set_visible(false);
+    }
     progress_stack.add_named(&progress_text_box, Some("progress_text"));
 
     progress_dialog.set_extra_child(Some(&progress_stack));
@@ -149,6 +833,22 @@ pub fn present_receive_transfer_ui(
         device_name_box
     }
 
+    fn create_progress_file_row(file_name: &str) -> adw::ActionRow {
+        let (content_type, _) = gio::content_type_guess(Some(file_name), None::<&[u8]>);
+        let file_icon = gtk::Image::builder()
+            .gicon(&gio::content_type_get_symbolic_icon(&content_type))
+            .build();
+
+        let row = adw::ActionRow::builder()
+            .title(file_name)
+            .use_markup(false)
+            .title_lines(1)
+            .build();
+        row.add_prefix(&file_icon);
+
+        row
+    }
+
     let consent_dialog = adw::AlertDialog::builder()
         .heading(&gettext("Incoming Transfer"))
         .width_request(200)
@@ -167,11 +867,26 @@ pub fn present_receive_transfer_ui(
         auto_decline_ctk,
         #[strong]
         notification_id,
+        #[strong]
+        download_folder_override,
+        #[strong]
+        receive_start_timeout_ctk,
+        #[strong]
+        connection_loss_ctk,
+        #[strong]
+        consent_toast,
+        #[strong]
+        accepted_at,
+        #[weak]
+        progress_bar,
         move |receive_state| {
             // Cancel auto-decline
             if !auto_decline_ctk.is_cancelled() {
                 auto_decline_ctk.cancel();
             }
+            if let Some(toast) = consent_toast.take() {
+                toast.dismiss();
+            }
 
             let event = receive_state
                 .event()
@@ -180,36 +895,151 @@ pub fn present_receive_transfer_ui(
                 Some(UserAction::ConsentAccept) => {
                     consent_dialog.close();
 
-                    win.imp()
-                        .rqs
-                        .blocking_lock()
-                        .as_mut()
-                        .unwrap()
-                        .message_sender
-                        .send(rqs_lib::channel::ChannelMessage {
-                            id: event.id.to_string(),
-                            msg: rqs_lib::channel::Message::Lib {
-                                action: rqs_lib::channel::TransferAction::ConsentAccept,
-                            },
-                        })
-                        .unwrap();
+                    if win.is_trusted_only() {
+                        win.confirm_trusted_device(&event.device_name());
+                    }
 
-                    // Update the notification
-                    spawn_notification(
-                        notification_id.clone(),
-                        Notification::new(&event.device_name())
-                            .body(gettext("Receiving...").as_str())
-                            .priority(Priority::High)
-                            .display_hint([DisplayHint::Persistent])
-                            .default_action(None)
-                            .button(ashpd::desktop::notification::Button::new(
-                                &gettext("Cancel"),
-                                "transfer-cancel",
-                            )),
-                    );
+                    let download_folder = win.download_folder_for_device(&event.device_name());
+                    let is_organized_by_date = win.imp().settings.boolean("organize-by-date");
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        win,
+                        #[weak]
+                        receive_state,
+                        #[weak]
+                        progress_dialog,
+                        #[weak]
+                        progress_bar,
+                        #[strong]
+                        notification_id,
+                        #[strong]
+                        download_folder_override,
+                        #[strong]
+                        receive_start_timeout_ctk,
+                        #[strong]
+                        connection_loss_ctk,
+                        #[strong]
+                        accepted_at,
+                        async move {
+                            // The folder might be on e.g. a slow network mount
+                            let download_folder = match tokio_runtime()
+                                .spawn_blocking(move || {
+                                    prepare_download_folder(download_folder, is_organized_by_date)
+                                })
+                                .await
+                            {
+                                Ok(it) => it,
+                                Err(err) => {
+                                    tracing::error!(%err, "Couldn't prepare the download folder");
+                                    return;
+                                }
+                            };
+                            let download_folder = match download_folder {
+                                Ok(it) => it,
+                                Err(download_folder) => {
+                                    present_unwritable_folder_dialog(&win, &download_folder);
+                                    receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                                    return;
+                                }
+                            };
+
+                            // Answered some other way while the folder was being checked
+                            if receive_state.user_action() != Some(UserAction::ConsentAccept) {
+                                return;
+                            }
+
+                            let mut rqs_guard = win.imp().rqs.lock().await;
+                            let Some(rqs) = rqs_guard.as_mut() else {
+                                tracing::warn!(
+                                    "Can't accept the transfer, the service isn't running"
+                                );
+                                return;
+                            };
+                            accepted_at.set(Some(SystemTime::now()));
+                            // rqs_lib only has a single download path, so it's swapped for the
+                            // duration of the transfer. Concurrent transfers from other devices
+                            // will end up in this folder too.
+                            if download_folder.as_os_str()
+                                != win.imp().settings.string("download-folder").as_str()
+                            {
+                                tracing::debug!(
+                                    ?download_folder,
+                                    "Receiving into a folder other than the default"
+                                );
+                                rqs.set_download_path(Some(download_folder.clone()));
+                                download_folder_override.replace(Some(download_folder));
+                            }
+
+                            rqs.message_sender
+                                .send(rqs_lib::channel::ChannelMessage {
+                                    id: event.id.to_string(),
+                                    msg: rqs_lib::channel::Message::Lib {
+                                        action: rqs_lib::channel::TransferAction::ConsentAccept,
+                                    },
+                                })
+                                .unwrap();
+                            drop(rqs_guard);
+
+                            // Update the notification
+                            if should_show_notification(&win, NotificationKind::Progress) {
+                                spawn_notification(
+                                    notification_id.clone(),
+                                    Notification::new(&event.display_device_name())
+                                        .body(gettext("Receiving...").as_str())
+                                        .priority(Priority::High)
+                                        .display_hint([DisplayHint::Persistent])
+                                        .default_action(None)
+                                        .button(ashpd::desktop::notification::Button::new(
+                                            &gettext("Cancel"),
+                                            "transfer-cancel",
+                                        )),
+                                );
+                            }
+
+                            if is_toast_ui {
+                                let imp = win.imp();
+                                imp.bottom_bar_receive_progress_label.set_label(
+                                    &formatx!(
+                                        gettext("Receiving from {}"),
+                                        event.display_device_name()
+                                    )
+                                    .unwrap_or_else(|_| "badly formatted locale string".into()),
+                                );
+                                progress_bar
+                                    .bind_property(
+                                        "fraction",
+                                        &imp.bottom_bar_receive_progress_bar.get(),
+                                        "fraction",
+                                    )
+                                    .sync_create()
+                                    .build();
+                                imp.bottom_bar_receive_progress.set_visible(true);
+                            } else {
+                                // Spawn progress dialog
+                                progress_dialog.present(Some(&win));
+                            }
 
-                    // Spawn progress dialog
-                    progress_dialog.present(Some(&win));
+                            // rqs_lib won't report anything if the sender dropped off the
+                            // network right after the accept, so give up after a while
+                            receive_start_timeout_ctk.replace(Some(cancel_receive_on(
+                                &win,
+                                &receive_state,
+                                notification_id.clone(),
+                                futures_timer::Delay::new(RECEIVE_START_TIMEOUT),
+                                gettext("The sender stopped responding"),
+                            )));
+
+                            // The sender won't be able to tell us it's gone once the
+                            // network is, so don't wait on rqs_lib to time out
+                            connection_loss_ctk.replace(Some(cancel_receive_on(
+                                &win,
+                                &receive_state,
+                                notification_id,
+                                monitors::wait_for_network_loss(win.subscribe_connectivity()),
+                                gettext("Connection lost"),
+                            )));
+                        }
+                    ));
                 }
                 Some(UserAction::ConsentDecline) => {
                     consent_dialog.close();
@@ -236,19 +1066,14 @@ pub fn present_receive_transfer_ui(
 
                     is_user_cancelled.replace(true);
 
-                    win.imp()
-                        .rqs
-                        .blocking_lock()
-                        .as_mut()
-                        .unwrap()
-                        .message_sender
-                        .send(rqs_lib::channel::ChannelMessage {
-                            id: event.id.to_string(),
-                            msg: rqs_lib::channel::Message::Lib {
-                                action: rqs_lib::channel::TransferAction::TransferCancel,
-                            },
-                        })
-                        .unwrap();
+                    let id = event.id.to_string();
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        win,
+                        async move {
+                            win.send_transfer_cancel(id).await;
+                        }
+                    ));
                 }
                 None => {}
             };
@@ -260,14 +1085,65 @@ pub fn present_receive_transfer_ui(
         win,
         #[strong]
         notification_id,
+        #[strong]
+        download_folder_override,
+        #[strong]
+        receive_start_timeout_ctk,
+        #[strong]
+        connection_loss_ctk,
+        #[strong]
+        rename_to,
+        #[strong]
+        accepted_at,
+        #[strong]
+        consent_toast,
         move |receive_state| {
             use rqs_lib::TransferState;
 
+            let restore_download_folder = || {
+                if download_folder_override.take().is_some() {
+                    win.restore_download_folder();
+                }
+            };
+
             let event_msg = receive_state.event().expect("ReceiveTransferState.event must be set");
             let client_msg = event_msg.msg.as_client_unchecked();
             let metadata = client_msg.metadata.as_ref().unwrap();
 
-            match client_msg.state.clone().unwrap_or(TransferState::Initial) {
+            let state = client_msg.state.clone().unwrap_or(TransferState::Initial);
+            if matches!(
+                state,
+                TransferState::ReceivingFiles
+                    | TransferState::Finished
+                    | TransferState::Cancelled
+                    | TransferState::Disconnected
+                    | TransferState::Rejected
+            ) && let Some(ctk) = receive_start_timeout_ctk.take()
+            {
+                ctk.cancel();
+            }
+            if matches!(
+                state,
+                TransferState::Finished
+                    | TransferState::Cancelled
+                    | TransferState::Disconnected
+                    | TransferState::Rejected
+            ) && let Some(ctk) = connection_loss_ctk.take()
+            {
+                ctk.cancel();
+            }
+            if matches!(
+                state,
+                TransferState::Finished
+                    | TransferState::Cancelled
+                    | TransferState::Disconnected
+                    | TransferState::Rejected
+            ) && is_toast_ui
+            {
+                win.imp().bottom_bar_receive_progress.set_visible(false);
+            }
+
+            match state {
                 TransferState::Initial => {}
                 TransferState::ReceivedConnectionRequest => {}
                 TransferState::SentUkeyServerInit => {}
@@ -280,6 +1156,70 @@ pub fn present_receive_transfer_ui(
                 TransferState::SentIntroduction => {}
                 TransferState::ReceivedPairedKeyResult => {}
                 TransferState::WaitingForUserConsent => {
+                    // While trusted-only, everyone but trusted devices is turned away.
+                    // Trusted devices are let in right away once they were accepted
+                    // by hand this session, since they're only known by the name.
+                    let is_trusted_only = win.is_trusted_only();
+                    let is_trusted =
+                        is_trusted_only && win.is_trusted_device(&event_msg.device_name());
+                    if is_trusted_only && !is_trusted {
+                        tracing::info!(
+                            device_name = %event_msg.device_name(),
+                            "Declining request from untrusted device"
+                        );
+                        receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                        return;
+                    }
+
+                    let scheduled_action = if is_trusted {
+                        None
+                    } else {
+                        win.scheduled_auto_response()
+                    };
+                    if scheduled_action == Some(UserAction::ConsentDecline) {
+                        tracing::info!(
+                            device_name = %event_msg.device_name(),
+                            "Declining request as scheduled"
+                        );
+                        receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                        return;
+                    }
+
+                    let blocked_files = event_msg
+                        .files()
+                        .map(|files| win.blocked_files(files))
+                        .unwrap_or_default();
+                    if !blocked_files.is_empty() {
+                        tracing::info!(
+                            device_name = %event_msg.device_name(),
+                            ?blocked_files,
+                            "Request has files with blocked extensions"
+                        );
+
+                        if win
+                            .imp()
+                            .settings
+                            .boolean("auto-decline-blocked-extensions")
+                        {
+                            receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                            win.imp().toast_overlay.add_toast(adw::Toast::new(
+                                &formatx!(
+                                    gettext("Declined files of a blocked type from {}"),
+                                    event_msg.display_device_name()
+                                )
+                                .unwrap_or_else(|_| "badly formatted locale string".into()),
+                            ));
+                            return;
+                        }
+                    }
+
+                    let is_confirmed_trusted =
+                        is_trusted && win.is_confirmed_trusted_device(&event_msg.device_name());
+                    // Blocked files are always asked about, even from trusted devices
+                    let is_auto_accepted = blocked_files.is_empty()
+                        && (is_confirmed_trusted
+                            || scheduled_action == Some(UserAction::ConsentAccept));
+
                     consent_dialog.add_responses(&[
                         ("decline", &gettext("Decline")),
                         ("accept", &gettext("Accept")),
@@ -287,7 +1227,9 @@ pub fn present_receive_transfer_ui(
                     consent_dialog
                         .set_response_appearance("accept", adw::ResponseAppearance::Suggested);
 
-                    consent_dialog.set_default_response(Some("decline"));
+                    // Enter accepts, while Escape goes through "close" and
+                    // declines
+                    consent_dialog.set_default_response(Some("accept"));
                     consent_dialog.set_close_response("close");
 
                     let info_box = gtk::Box::builder()
@@ -297,13 +1239,13 @@ pub fn present_receive_transfer_ui(
                         .build();
                     consent_dialog.set_extra_child(Some(&info_box));
 
-                    let device_name = event_msg.device_name();
+                    let device_name = event_msg.display_device_name();
 
                     let device_name_box = create_device_name_box(&device_name);
                     info_box.append(&device_name_box);
 
                     let total_bytes = metadata.total_bytes;
-                    let transfer_size = human_bytes::human_bytes(total_bytes as f64);
+                    let transfer_size = utils::format_size_localized(total_bytes);
 
                     if let Some(files) = event_msg.files() {
                         let file_count = files.len();
@@ -312,7 +1254,7 @@ pub fn present_receive_transfer_ui(
                             .label(
                                 formatx!(
                                     ngettext(
-                                        // Translators: An e.g. "6 Files (42.3MB)"
+                                        // Translators: An e.g. "6 Files (42.3 MB)"
                                         "{} file ({})",
                                         "{} files ({})",
                                         file_count as u32,
@@ -326,6 +1268,69 @@ pub fn present_receive_transfer_ui(
                             .css_classes(["dimmed", "heading"])
                             .build();
                         info_box.append(&files_label);
+
+                        if !blocked_files.is_empty() {
+                            let blocked_files_label = gtk::Label::builder()
+                                .label(
+                                    formatx!(
+                                        ngettext(
+                                            "Blocked file type: {}",
+                                            "Blocked file types: {}",
+                                            blocked_files.len() as u32,
+                                        ),
+                                        blocked_files.join(", ")
+                                    )
+                                    .unwrap_or_else(|_| "badly formatted locale string".into()),
+                                )
+                                .wrap(true)
+                                .lines(3)
+                                .ellipsize(gtk::pango::EllipsizeMode::End)
+                                .max_width_chars(36)
+                                .justify(gtk::Justification::Center)
+                                .halign(gtk::Align::Center)
+                                .css_classes(["warning"])
+                                .build();
+                            info_box.append(&blocked_files_label);
+                        }
+
+                        if let [file_name] = files.as_slice()
+                            && let Some(file_name) = Path::new(file_name).file_name()
+                        {
+                            let file_name = file_name.to_string_lossy().into_owned();
+
+                            let rename_listbox = gtk::ListBox::builder()
+                                .selection_mode(gtk::SelectionMode::None)
+                                .css_classes(["boxed-list"])
+                                .margin_top(4)
+                                .build();
+                            let rename_row = adw::EntryRow::builder()
+                                .title(&gettext("Save As"))
+                                .text(&file_name)
+                                .activates_default(true)
+                                .build();
+                            rename_listbox.append(&rename_row);
+                            info_box.append(&rename_listbox);
+
+                            rename_row.connect_changed(clone!(
+                                #[strong]
+                                rename_to,
+                                move |row| {
+                                    let new_name = row.text().trim().to_string();
+                                    let is_valid = !new_name.is_empty()
+                                        && new_name != "."
+                                        && new_name != ".."
+                                        && !new_name.chars().any(std::path::is_separator);
+                                    if is_valid {
+                                        row.remove_css_class("error");
+                                    } else {
+                                        row.add_css_class("error");
+                                    }
+
+                                    *rename_to.borrow_mut() =
+                                        (is_valid && new_name != file_name).then_some(new_name);
+                                }
+                            ));
+                        }
                     } else {
                         let text_info_label = gtk::Label::builder()
                             .ellipsize(gtk::pango::EllipsizeMode::End)
@@ -343,6 +1348,14 @@ pub fn present_receive_transfer_ui(
                         info_box.append(&text_info_label);
                     }
 
+                    let pin_code = client_msg
+                        .metadata
+                        .as_ref()
+                        .map(|it| it.pin_code.as_ref().map(|it| it.as_str()))
+                        .flatten()
+                        .unwrap_or("???");
+                    // Selectable so that it can be focused and read out, to be
+                    // checked against the one on the sending device
                     let pincode_label = gtk::Label::builder()
                         .label(
                             formatx!(
@@ -350,19 +1363,27 @@ pub fn present_receive_transfer_ui(
                                     // Translators: This is the pin-code for the transfer
                                     "Code: {}"
                                 ),
-                                client_msg
-                                    .metadata
-                                    .as_ref()
-                                    .map(|it| it.pin_code.as_ref().map(|it| it.as_str()))
-                                    .flatten()
-                                    .unwrap_or("???")
+                                pin_code
                             )
                             .unwrap_or_else(|_| "badly formatted locale string".into()),
                         )
+                        .selectable(true)
+                        .css_classes(["monospace", "title-3"])
+                        .build();
+                    pincode_label.update_property(&[gtk::accessible::Property::Label(
+                        &formatx!(gettext("Verification code {}"), pin_code)
+                            .unwrap_or_else(|_| "badly formatted locale string".into()),
+                    )]);
+                    let pincode_box = gtk::Box::builder()
                         .halign(gtk::Align::Center)
-                        .css_classes(["dimmed", "monospace"])
+                        .spacing(6)
                         .build();
-                    info_box.append(&pincode_label);
+                    pincode_box.append(&pincode_label);
+                    let pin_code = pin_code.to_string();
+                    pincode_box.append(&create_pincode_copy_button(move || {
+                        Some(pin_code.clone())
+                    }));
+                    info_box.append(&pincode_box);
 
                     consent_dialog.connect_response(
                         None,
@@ -392,39 +1413,46 @@ pub fn present_receive_transfer_ui(
                         ),
                     );
 
-                    // TODO: Maybe make this configurable?
-                    // Timeout: auto-decline after 1 minute
+                    // Timeout: auto-decline after `consent-timeout-seconds` (1 minute by default)
                     // Since we can't know if the user has simply closed the notification,
                     // we can't use it as a decline response unfortunately. The solution is
-                    // to have a 1min timeout for incoming requests.
-                    glib::spawn_future_local(clone!(
-                        #[weak]
-                        win,
-                        #[strong]
-                        receive_state,
-                        #[strong]
-                        auto_decline_ctk,
-                        async move {
-                            tokio::select! {
-                                _ = futures_timer::Delay::new(Duration::from_mins(1)) => {
-                                    if receive_state.user_action().is_none() {
-                                        receive_state.set_user_action(Some(UserAction::ConsentDecline));
-                                        win.imp().toast_overlay.add_toast(adw::Toast::new(&gettext("Request timed out")));
+                    // to have a timeout for incoming requests.
+                    //
+                    // A timeout of 0 means the request waits indefinitely.
+                    let consent_timeout_secs =
+                        win.imp().settings.int("consent-timeout-seconds").max(0) as u64;
+                    if consent_timeout_secs > 0 {
+                        glib::spawn_future_local(clone!(
+                            #[weak]
+                            win,
+                            #[strong]
+                            receive_state,
+                            #[strong]
+                            auto_decline_ctk,
+                            async move {
+                                tokio::select! {
+                                    _ = futures_timer::Delay::new(Duration::from_secs(consent_timeout_secs)) => {
+                                        if receive_state.user_action().is_none() {
+                                            receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                                            if win.imp().settings.boolean("show-timeout-toast") {
+                                                win.imp().toast_overlay.add_toast(adw::Toast::new(&gettext("Request timed out")));
+                                            }
+                                        }
                                     }
+                                    _ = auto_decline_ctk.cancelled() => {}
                                 }
-                                _ = auto_decline_ctk.cancelled() => {}
                             }
-                        }
-                    ));
+                        ));
+                    }
 
-                    let body = formatx!(
+                    let mut body = formatx!(
                         gettext(
                             // Translators: This is when some device is sharing files or text
                             // e.g. (Someone's Phone wants to share 4 files)
                             // e.g. (Someone's Phone wants to share "lorem ipsum ...")
                             "{} wants to share {}"
                         ),
-                        event_msg.device_name(),
+                        event_msg.display_device_name(),
                         if let Some(files) = event_msg.files() {
                             formatx!(
                                 ngettext("{} File", "{} Files", files.len() as u32),
@@ -441,42 +1469,129 @@ pub fn present_receive_transfer_ui(
                         }
                     )
                     .unwrap_or_default();
+                    if !blocked_files.is_empty() {
+                        body.push('\n');
+                        body.push_str(&gettext("Includes files of a blocked type"));
+                    }
+
+                    // The sender and what's being shared are otherwise only in
+                    // the extra child, which screen readers won't announce
+                    // along with the heading
+                    consent_dialog
+                        .update_property(&[gtk::accessible::Property::Description(&body)]);
 
                     // Use a static id, like the app id
                     // There will only be one request at a time anyways
                     // And, we'll also need to close the notification on exit
                     // or it'll persist otherwise
-                    spawn_notification(
-                        notification_id.clone(),
-                        Notification::new(&gettext("Incoming Transfer"))
-                            .default_action("accept")
-                            .body(body.as_str())
-                            .priority(Priority::High)
-                            // Persistent doesn't work (the close button is still there), atleast with gnome portal
-                            .display_hint([DisplayHint::Persistent])
-                            .button(ashpd::desktop::notification::Button::new(
-                                &gettext("Decline"),
-                                "consent-decline",
-                            ))
-                            .button(ashpd::desktop::notification::Button::new(
-                                &gettext("Accept"),
-                                "consent-accept",
-                            )),
-                    );
+                    if !is_auto_accepted
+                        && should_show_notification(&win, NotificationKind::Progress)
+                    {
+                        spawn_notification(
+                            notification_id.clone(),
+                            Notification::new(&gettext("Incoming Transfer"))
+                                .default_action("accept")
+                                .body(body.as_str())
+                                .priority(Priority::High)
+                                // Persistent doesn't work (the close button is still there), atleast with gnome portal
+                                .display_hint([DisplayHint::Persistent])
+                                .button(ashpd::desktop::notification::Button::new(
+                                    &gettext("Block & Go Invisible"),
+                                    "consent-decline-invisible",
+                                ))
+                                .button(ashpd::desktop::notification::Button::new(
+                                    &gettext("Decline"),
+                                    "consent-decline",
+                                ))
+                                .button(ashpd::desktop::notification::Button::new(
+                                    &gettext("Accept"),
+                                    "consent-accept",
+                                )),
+                        );
+                    }
 
-                    consent_dialog.present(Some(&win));
+                    if !is_auto_accepted && is_toast_ui {
+                        let title_box = gtk::Box::builder().spacing(12).build();
+                        title_box.append(
+                            &gtk::Label::builder()
+                                .label(body.as_str())
+                                .wrap(true)
+                                .hexpand(true)
+                                .xalign(0.)
+                                .build(),
+                        );
+                        let decline_button = gtk::Button::builder()
+                            .label(&gettext("Decline"))
+                            .valign(gtk::Align::Center)
+                            .css_classes(["flat"])
+                            .build();
+                        title_box.append(&decline_button);
+
+                        let toast = adw::Toast::builder()
+                            .custom_title(&title_box)
+                            .button_label(&gettext("Accept"))
+                            .priority(adw::ToastPriority::High)
+                            .timeout(0)
+                            .build();
+                        toast.connect_button_clicked(clone!(
+                            #[weak]
+                            receive_state,
+                            move |_| {
+                                receive_state.set_user_action(Some(UserAction::ConsentAccept));
+                            }
+                        ));
+                        decline_button.connect_clicked(clone!(
+                            #[weak]
+                            receive_state,
+                            move |_| {
+                                receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                            }
+                        ));
+                        // Same as closing the dialog
+                        toast.connect_dismissed(clone!(
+                            #[weak]
+                            receive_state,
+                            move |_| {
+                                if receive_state.user_action().is_none() {
+                                    receive_state.set_user_action(Some(UserAction::ConsentDecline));
+                                }
+                            }
+                        ));
+
+                        consent_toast.replace(Some(toast.clone()));
+                        win.imp().toast_overlay.add_toast(toast);
+                    } else if !is_auto_accepted {
+                        consent_dialog.present(Some(&win));
+                    }
 
                     // TODO: show a progress dialog for both but with a delay?
                     // Create Progress bar dialog
                     let total_bytes = metadata.total_bytes;
-                    receive_state
-                        .imp()
-                        .eta
-                        .borrow_mut()
-                        .prepare_for_new_transfer(Some(total_bytes as usize));
+                    *receive_state.imp().eta.borrow_mut() =
+                        win.new_eta_estimator(total_bytes as usize);
+                    progress_files_listbox.remove_all();
+                    if let Some(files) = event_msg.files()
+                        && files.len() > 1
+                    {
+                        for file_name in files {
+                            progress_files_listbox.append(&create_progress_file_row(file_name));
+                        }
+                        progress_files_scrolled_window.set_visible(true);
+                    } else {
+                        progress_files_scrolled_window.set_visible(false);
+                    }
                     if event_msg.is_text_type() {
                         progress_stack.set_visible_child_name("progress_text");
                     }
+
+                    if is_auto_accepted {
+                        tracing::info!(
+                            device_name = %event_msg.device_name(),
+                            is_trusted,
+                            "Accepting request automatically"
+                        );
+                        receive_state.set_user_action(Some(UserAction::ConsentAccept));
+                    }
                 }
                 TransferState::ReceivingFiles => {
                     if !event_msg.is_text_type() {
@@ -489,10 +1604,18 @@ pub fn present_receive_transfer_ui(
                                     .step_with(meta.ack_bytes as usize);
 
                                 if meta.total_bytes > 0 {
-                                    progress_bar.set_fraction(
-                                        meta.ack_bytes as f64 / meta.total_bytes as f64,
+                                    let fraction = meta.ack_bytes as f64 / meta.total_bytes as f64;
+                                    progress_bar.set_fraction(fraction);
+                                    percentage_label.set_label(
+                                        &formatx!(
+                                            // Translators: A progress percentage, e.g. "73%"
+                                            gettext("{}%"),
+                                            (fraction * 100.0).floor() as u32
+                                        )
+                                        .unwrap_or_else(|_| "badly formatted locale string".into()),
                                     );
                                 }
+                                percentage_label.set_visible(meta.total_bytes > 0);
                             }
 
                             formatx!(
@@ -517,6 +1640,7 @@ pub fn present_receive_transfer_ui(
                 TransferState::SendingFiles => {}
                 TransferState::Disconnected => {
                     if event_msg.id == init_id {
+                        restore_download_folder();
                         progress_dialog.set_can_close(true);
                         if let Some(UserAction::ConsentAccept) = receive_state.user_action() {
                             progress_dialog.close();
@@ -524,15 +1648,23 @@ pub fn present_receive_transfer_ui(
                             consent_dialog.close();
                         }
 
-                        let body = gettext("Unexpected dissconnection");
-
-                        spawn_notification(
-                            notification_id.clone(),
-                            Notification::new(&event_msg.device_name())
-                                .body(body.as_str())
-                                .priority(Priority::High)
-                                .default_action(None)
-                        );
+                        let body = gettext("Unexpected disconnection");
+                        // Rather than leaving the user guessing, the usual culprit
+                        // being a device that went out of range or off the network
+                        let hint = gettext("The sender may have left the network");
+
+                        if should_show_notification(&win, NotificationKind::Completion) {
+                            spawn_notification(
+                                notification_id.clone(),
+                                Notification::new(&event_msg.display_device_name())
+                                    .body(format!("{body}\n{hint}").as_str())
+                                    .priority(Priority::High)
+                                    .default_action(None)
+                                    .button(
+                                        ashpd::desktop::notification::Button::new(&gettext("Dismiss"), "dismiss")
+                                    )
+                            );
+                        }
 
                         win.imp().toast_overlay.add_toast(
                             adw::Toast::builder()
@@ -541,13 +1673,11 @@ pub fn present_receive_transfer_ui(
                                 .build(),
                         );
 
-                        // FIXME: If ReceivingFiles is not received within 5~10 seconds of an Accept,
-                        // reject request and show this error, it's usually because the sender
-                        // disconnected from the network
                     }
                 }
                 TransferState::Rejected => {}
                 TransferState::Cancelled => {
+                    restore_download_folder();
                     progress_dialog.set_can_close(true);
                     if let Some(UserAction::ConsentAccept) = receive_state.user_action() {
                         progress_dialog.close();
@@ -559,13 +1689,15 @@ pub fn present_receive_transfer_ui(
                     if !is_user_cancelled.get() {
                         let body = gettext("Transfer cancelled by sender");
 
-                        spawn_notification(
-                            notification_id.clone(),
-                            Notification::new(&event_msg.device_name())
-                                .body(body.as_str())
-                                .priority(Priority::High)
-                                .default_action(None)
-                        );
+                        if should_show_notification(&win, NotificationKind::Completion) {
+                            spawn_notification(
+                                notification_id.clone(),
+                                Notification::new(&event_msg.display_device_name())
+                                    .body(body.as_str())
+                                    .priority(Priority::High)
+                                    .default_action(None)
+                            );
+                        }
 
                         win.imp().toast_overlay.add_toast(
                             adw::Toast::builder()
@@ -576,6 +1708,8 @@ pub fn present_receive_transfer_ui(
                     }
                 }
                 TransferState::Finished => {
+                    let download_folder = download_folder_override.borrow().clone();
+                    restore_download_folder();
                     progress_dialog.set_can_close(true);
                     if let Some(UserAction::ConsentAccept) = receive_state.user_action() {
                         progress_dialog.close();
@@ -615,8 +1749,16 @@ pub fn present_receive_transfer_ui(
                             .tooltip_text(&gettext("Save text as file"))
                             .css_classes(["circular", "flat"])
                             .build();
+                        let edit_text_button = gtk::ToggleButton::builder()
+                            .visible(false)
+                            .valign(gtk::Align::Center)
+                            .icon_name("document-edit-symbolic")
+                            .tooltip_text(&gettext("Edit text"))
+                            .css_classes(["circular", "flat"])
+                            .build();
                         header_bar.pack_start(&copy_text_button);
                         header_bar.pack_start(&save_text_button);
+                        header_bar.pack_end(&edit_text_button);
 
                         let clamp = adw::Clamp::builder()
                             .maximum_size(550)
@@ -665,6 +1807,14 @@ pub fn present_receive_transfer_ui(
                             .build();
                         root_box.append(&text_view_frame);
 
+                        let edit_hint_label = gtk::Label::builder()
+                            .label(&gettext("Edits only change your copy of the text"))
+                            .wrap(true)
+                            .visible(false)
+                            .css_classes(["caption", "dimmed"])
+                            .build();
+                        root_box.append(&edit_hint_label);
+
                         let open_uri_button = gtk::Button::builder()
                             .halign(gtk::Align::Center)
                             .valign(gtk::Align::Center)
@@ -692,7 +1842,11 @@ pub fn present_receive_transfer_ui(
                                 );
 
                                 glib::spawn_future_local(async move {
-                                    let file = gtk::FileDialog::new()
+                                    let file = gtk::FileDialog::builder()
+                                        .initial_folder(&gio::File::for_path(
+                                            win.text_save_folder(),
+                                        ))
+                                        .build()
                                         .save_text_file_future(Some(&win))
                                         .await
                                         .unwrap()
@@ -713,6 +1867,24 @@ pub fn present_receive_transfer_ui(
                             }
                         ));
 
+                        // The copy and save handlers read from the buffer, so they
+                        // pick up the edits as well
+                        edit_text_button.connect_toggled(clone!(
+                            #[weak]
+                            text_view,
+                            #[weak]
+                            edit_hint_label,
+                            move |button| {
+                                let is_editing = button.is_active();
+                                text_view.set_editable(is_editing);
+                                text_view.set_cursor_visible(is_editing);
+                                edit_hint_label.set_visible(is_editing);
+                                if is_editing {
+                                    text_view.grab_focus();
+                                }
+                            }
+                        ));
+
                         let clipboard = win.clipboard();
                         copy_text_button.connect_clicked(clone!(
                             #[weak]
@@ -752,41 +1924,84 @@ pub fn present_receive_transfer_ui(
                         let raw_text = text_data.0;
                         let text = if text_type.clone() as u32 == TextPayloadType::Text as u32 {
                             save_text_button.set_visible(true);
+                            edit_text_button.set_visible(true);
                             clean_text_payload(&raw_text)
                         } else {
                             &raw_text
                         };
                         text_view.set_buffer(Some(&gtk::TextBuffer::builder().text(text).build()));
 
-                        spawn_notification(
-                            notification_id.clone(),
-                            Notification::new(&event_msg.device_name())
-                                .body(
-                                    formatx!(
-                                        gettext("Received \"{}\""),
-                                        if text.len() > 48 {
-                                            format!("{}{}", &text[..48], "...")
-                                        } else {
-                                            text.into()
-                                        }
-                                    )
-                                    .unwrap_or_default()
-                                    .as_str()
-                                )
-                                .priority(Priority::High)
-                                .display_hint([DisplayHint::ShowAsNew])
-                                .default_action("copy-text")
-                                .default_action_target(text)
-                                .button(
-                                    ashpd::desktop::notification::Button::new(&gettext("Copy"), "copy-text")
-                                        .target(text)
+                        // URLs already get the Open button
+                        if text_type.clone() as u32 == TextPayloadType::Text as u32 {
+                            let urls = detect_urls(text);
+                            if !urls.is_empty() {
+                                root_box.append(&create_links_listbox(&win, &urls));
+                            }
+                        }
+
+                        let wifi_data = event_msg.transferred_wifi_data();
+                        if let Some(wifi_data) = &wifi_data {
+                            text_view_frame.set_visible(false);
+                            root_box.append(&create_wifi_credentials_listbox(&clipboard, wifi_data));
+                        }
+
+                        // Only the password is offered for copying in case of Wi-Fi,
+                        // since that's what'd be pasted into the network settings
+                        let (notification_body, copy_label, copy_target) = match &wifi_data {
+                            Some(wifi_data) => (
+                                formatx!(gettext("Received Wi-Fi network \"{}\""), &wifi_data.ssid)
+                                    .unwrap_or_default(),
+                                gettext("Copy Password"),
+                                wifi_data.password.as_str(),
+                            ),
+                            None => (
+                                formatx!(
+                                    gettext("Received \"{}\""),
+                                    if text.len() > 48 {
+                                        format!("{}{}", &text[..48], "...")
+                                    } else {
+                                        text.into()
+                                    }
                                 )
-                        );
+                                .unwrap_or_default(),
+                                gettext("Copy"),
+                                text,
+                            ),
+                        };
 
-                        // FIXME: Redo the Wi-Fi view when we've more info such as the Wi-Fi security type
-                        // and payload (password) available separately
+                        if should_show_notification(&win, NotificationKind::Completion) {
+                            spawn_notification(
+                                notification_id.clone(),
+                                Notification::new(&event_msg.display_device_name())
+                                    .body(notification_body.as_str())
+                                    .priority(Priority::High)
+                                    .display_hint([DisplayHint::ShowAsNew])
+                                    .default_action("copy-text")
+                                    .default_action_target(copy_target)
+                                    .button(
+                                        ashpd::desktop::notification::Button::new(&copy_label, "copy-text")
+                                            .target(copy_target)
+                                    )
+                            );
+                        }
 
-                        dialog.present(Some(&win));
+                        // Links and Wi-Fi networks still get the dialog, for
+                        // their Open and Copy Password buttons
+                        let auto_copy_max_chars =
+                            win.imp().settings.int("short-text-auto-copy").max(0) as usize;
+                        if text_type.clone() as u32 == TextPayloadType::Text as u32
+                            && text.chars().count() < auto_copy_max_chars
+                        {
+                            clipboard.set_text(text);
+                            win.imp().toast_overlay.add_toast(
+                                adw::Toast::builder()
+                                    .title(&gettext("Copied received text"))
+                                    .priority(adw::ToastPriority::High)
+                                    .build(),
+                            );
+                        } else {
+                            dialog.present(Some(&win));
+                        }
                     } else {
                         // Received Files
                         let file_count = event_msg.files().unwrap().len();
@@ -801,27 +2016,219 @@ pub fn present_receive_transfer_ui(
                         )
                             .unwrap_or_else(|_| "badly formatted locale string".into());
 
-                        let target = win.imp().settings.string("download-folder");
-                        spawn_notification(
-                            notification_id.clone(),
-                            Notification::new(&event_msg.device_name())
-                                .body(body.as_str())
-                                .priority(Priority::High)
-                                .display_hint([DisplayHint::ShowAsNew])
-                                .default_action("open-folder")
-                                .default_action_target(target.as_str())
-                                .button(
-                                    ashpd::desktop::notification::Button::new(&gettext("Open"), "open-folder")
-                                        .target(target.as_str())
-                                )
-                        );
-                        let toast = adw::Toast::builder()
-                            .title(&body)
-                            .button_label(&gettext("Open"))
-                            .action_name("win.received-files")
-                            .priority(adw::ToastPriority::High)
-                            .build();
-                        win.imp().toast_overlay.add_toast(toast);
+                        let target = download_folder
+                            .map(|it| it.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| win.imp().settings.string("download-folder").into());
+                        let file_names = event_msg.files().unwrap().clone();
+                        let rename = rename_to.take().zip(accepted_at.get());
+                        let device_name = event_msg.display_device_name();
+                        let expected_total_bytes = event_msg
+                            .msg
+                            .as_client_unchecked()
+                            .metadata
+                            .as_ref()
+                            .map(|it| it.total_bytes);
+                        let notification_id = notification_id.clone();
+
+                        // Looking up and renaming the files blocks on the file system
+                        glib::spawn_future_local(clone!(
+                            #[weak]
+                            win,
+                            async move {
+                                let (file_paths, rename_outcome) = tokio_runtime()
+                                    .spawn_blocking({
+                                        let target = target.clone();
+                                        move || {
+                                            let mut file_paths = received_file_paths(
+                                                Path::new(target.as_str()),
+                                                &file_names,
+                                            );
+
+                                            let rename_outcome = match (rename, file_paths.as_mut_slice()) {
+                                                (Some((new_name, accepted_at)), [file_path]) => {
+                                                    let old_path = file_path.clone();
+                                                    let result = rename_received_file(file_path, &new_name, accepted_at);
+                                                    if let Ok(Some(new_path)) = &result {
+                                                        *file_path = new_path.clone();
+                                                    }
+                                                    Some((old_path, new_name, result))
+                                                }
+                                                _ => None,
+                                            };
+
+                                            (file_paths, rename_outcome)
+                                        }
+                                    })
+                                    .await
+                                    .unwrap_or_else(|err| {
+                                        tracing::warn!(%err);
+                                        Default::default()
+                                    });
+
+                                if let Some((file_path, new_name, result)) = rename_outcome {
+                                    match result {
+                                        Ok(Some(_)) => {}
+                                        Ok(None) => {
+                                            tracing::warn!(?file_path, %new_name, "Not renaming a file from before the transfer");
+                                            win.imp().toast_overlay.add_toast(adw::Toast::new(&gettext(
+                                                "Couldn't find the received file to rename",
+                                            )));
+                                        }
+                                        Err(err) => {
+                                            tracing::warn!(?file_path, %new_name, "Failed to rename received file: {err:#}");
+                                            win.imp().toast_overlay.add_toast(adw::Toast::new(&gettext(
+                                                "Couldn't rename the received file",
+                                            )));
+                                        }
+                                    }
+                                }
+
+                                // Open a lone file directly, there's no point in making
+                                // the user look for it in the folder
+                                let single_file_path = match file_paths.as_slice() {
+                                    [file_path] if file_count == 1 => {
+                                        Some(file_path.to_string_lossy().into_owned())
+                                    }
+                                    _ => None,
+                                };
+
+                                if should_show_notification(&win, NotificationKind::Completion) {
+                                    let notification = match single_file_path.as_deref() {
+                                        Some(file_path) => Notification::new(&device_name)
+                                            .default_action("open-file")
+                                            .default_action_target(file_path)
+                                            .button(
+                                                ashpd::desktop::notification::Button::new(&gettext("Open"), "open-file")
+                                                    .target(file_path)
+                                            )
+                                            .button(
+                                                ashpd::desktop::notification::Button::new(&gettext("Copy Path"), "copy-path")
+                                                    .target(file_path)
+                                            ),
+                                        None => Notification::new(&device_name)
+                                            .default_action("open-folder")
+                                            .default_action_target(target.as_str())
+                                            .button(
+                                                ashpd::desktop::notification::Button::new(&gettext("Open"), "open-folder")
+                                                    .target(target.as_str())
+                                            ),
+                                    };
+
+                                    let notification = notification
+                                        .body(body.as_str())
+                                        .priority(Priority::High)
+                                        .display_hint([DisplayHint::ShowAsNew]);
+                                    let notification_id = notification_id.clone();
+                                    let file_paths = file_paths.clone();
+                                    glib::spawn_future_local(async move {
+                                        let icon = tokio_runtime()
+                                            .spawn_blocking(move || received_files_notification_icon(&file_paths))
+                                            .await
+                                            .inspect_err(|err| tracing::warn!(%err))
+                                            .ok()
+                                            .flatten();
+
+                                        spawn_notification(
+                                            notification_id,
+                                            match icon {
+                                                Some(icon) => notification.icon(icon),
+                                                // Left to the app icon
+                                                None => notification,
+                                            },
+                                        );
+                                    });
+                                }
+                                let toast = match single_file_path.as_deref() {
+                                    Some(file_path) => adw::Toast::builder()
+                                        .button_label(&gettext("Copy Path"))
+                                        .action_name("win.copy-path")
+                                        .action_target(&file_path.to_variant()),
+                                    None => adw::Toast::builder()
+                                        .button_label(&gettext("Open"))
+                                        .action_name("win.received-files"),
+                                }
+                                .title(&body)
+                                .priority(adw::ToastPriority::High)
+                                .build();
+                                win.imp().toast_overlay.add_toast(toast);
+
+                                if win.imp().settings.boolean("verify-received-files")
+                                    && let Some(expected_total_bytes) = expected_total_bytes
+                                {
+                                    let file_paths = file_paths.clone();
+                                    glib::spawn_future_local(clone!(
+                                        #[weak]
+                                        win,
+                                        async move {
+                                            let is_matching = tokio_runtime()
+                                                .spawn_blocking({
+                                                    let file_paths = file_paths.clone();
+                                                    move || {
+                                                        is_received_size_matching(
+                                                            &file_paths,
+                                                            file_count,
+                                                            expected_total_bytes,
+                                                        )
+                                                    }
+                                                })
+                                                .await
+                                                .inspect_err(|err| tracing::warn!(%err))
+                                                .ok()
+                                                .flatten();
+                                            if is_matching != Some(false) {
+                                                return;
+                                            }
+
+                                            tracing::warn!(
+                                                ?file_paths,
+                                                expected_total_bytes,
+                                                "Received files don't match the expected size"
+                                            );
+
+                                            win.imp().toast_overlay.add_toast(
+                                                adw::Toast::builder()
+                                                    .title(&gettext(
+                                                        "Some of the received files may be incomplete",
+                                                    ))
+                                                    .priority(adw::ToastPriority::High)
+                                                    .build(),
+                                            );
+                                        }
+                                    ));
+                                }
+
+                                // Only while the window is focused, having apps pop up
+                                // out of nowhere otherwise would be surprising
+                                let auto_open_path = if win.is_active() {
+                                    match win.imp().settings.string("auto-open-received").as_str() {
+                                        "folder" => Some(target.as_str()),
+                                        // Multiple files fall back to opening the folder
+                                        "file" => Some(single_file_path.as_deref().unwrap_or(target.as_str())),
+                                        _ => None,
+                                    }
+                                } else {
+                                    None
+                                };
+                                if let Some(path) = auto_open_path {
+                                    gtk::FileLauncher::new(Some(&gio::File::for_path(path))).launch(
+                                        Some(&win),
+                                        None::<&gio::Cancellable>,
+                                        move |_| {},
+                                    );
+                                }
+
+                                match file_paths.as_slice() {
+                                    _ if auto_open_path.is_some() => {}
+                                    // Stays out of the way, the toast can open the files instead
+                                    _ if is_toast_ui => {}
+                                    [] => {}
+                                    [file_path] if file_count == 1 && is_image_file(file_path) => {
+                                        present_received_image_dialog(&win, file_path);
+                                    }
+                                    _ => present_received_files_dialog(&win, &file_paths),
+                                }
+                            }
+                        ));
                     }
                 }
             }
@@ -1,19 +1,51 @@
 use crate::{
     ext::MessageExt,
+    monitors,
     objects::{self, TransferState, send_transfer::SendRequestState},
-    tokio_runtime,
+    tokio_runtime, utils,
     window::PacketApplicationWindow,
 };
 
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use formatx::formatx;
 use gettextrs::{gettext, ngettext};
 use gtk::{gio, glib, glib::clone};
-use rqs_lib::channel::{ChannelMessage, MessageClient};
+use rqs_lib::channel::MessageClient;
+use tokio_util::sync::CancellationToken;
+
+/// How long to wait for the receiver to start the transfer once requested.
+///
+/// Has to outlast the consent prompt on the receiving end, which Packet
+/// declines by itself after a minute by default.
+const SEND_START_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How long a retry waits for a recipient that went away to be rediscovered.
+const RETRY_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// e.g. "Last sent 2 hours ago, 3 times"
+pub fn format_recipient_history(entry: &objects::RecipientHistoryEntry) -> String {
+    let elapsed =
+        utils::format_relative_time(std::time::UNIX_EPOCH + Duration::from_secs(entry.last_sent));
+
+    formatx!(
+        ngettext(
+            // Translators: e.g. "Last sent 2 hours ago, 3 times"
+            "Last sent {}, {} time",
+            "Last sent {}, {} times",
+            entry.send_count
+        ),
+        elapsed,
+        entry.send_count
+    )
+    .unwrap_or_else(|_| "badly formatted locale string".into())
+}
 
 fn get_model_item_from_listbox_row<T>(
-    model: &gio::ListStore,
+    model: &impl IsA<gio::ListModel>,
     list_box: &gtk::ListBox,
     row: &gtk::ListBoxRow,
 ) -> Option<T>
@@ -36,7 +68,7 @@ where
 /// Don't try to reuse a ListBoxRow...\
 /// ListBoxRow can be attached to a different model's widget
 fn get_listbox_row_from_model_item<T>(
-    model: &gio::ListStore,
+    model: &impl IsA<gio::ListModel>,
     list_box: &gtk::ListBox,
     model_item: &T,
 ) -> Option<gtk::ListBoxRow>
@@ -54,6 +86,53 @@ where
     list_box.row_at_index(pos as i32)
 }
 
+/// For reading the pin-code out to, or pasting it to, the other side. The
+/// icon turns into a check mark for a moment once it's copied, as the dialogs
+/// it's shown in have no toasts.
+pub fn create_pincode_copy_button(pin_code: impl Fn() -> Option<String> + 'static) -> gtk::Button {
+    let button = gtk::Button::builder()
+        .valign(gtk::Align::Center)
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text(&gettext("Copy Code"))
+        .css_classes(["flat", "circular"])
+        .build();
+    button.connect_clicked(move |button| {
+        let Some(pin_code) = pin_code() else {
+            return;
+        };
+        button.clipboard().set_text(&pin_code);
+
+        button.set_icon_name("object-select-symbolic");
+        glib::timeout_add_local_once(
+            Duration::from_secs(2),
+            clone!(
+                #[weak]
+                button,
+                move || button.set_icon_name("edit-copy-symbolic")
+            ),
+        );
+    });
+
+    button
+}
+
+/// Drops a lingering recipient from the list, it'll be added back if it's
+/// discovered again.
+///
+/// It's removed from the underlying model, so the sorted model and the rows
+/// stay in step for the index lookups above.
+fn dismiss_recipient(win: &PacketApplicationWindow, model_item: &SendRequestState) {
+    let imp = win.imp();
+
+    let id = model_item.endpoint_info().id.clone();
+    tracing::info!(%id, "Dismissing recipient");
+
+    if let Some(pos) = imp.recipient_model.find(model_item) {
+        imp.recipient_model.remove(pos);
+    }
+    imp.send_transfers_id_cache.blocking_lock().remove(&id);
+}
+
 pub fn handle_recipient_card_clicked(
     win: &PacketApplicationWindow,
     list_box: &gtk::ListBox,
@@ -61,9 +140,17 @@ pub fn handle_recipient_card_clicked(
 ) {
     let imp = win.imp();
 
-    let model_item =
-        get_model_item_from_listbox_row::<SendRequestState>(&imp.recipient_model, list_box, row)
-            .expect("Index should be valid since model and ListBox are related");
+    let model_item = get_model_item_from_listbox_row::<SendRequestState>(
+        &imp.recipient_sorted_model,
+        list_box,
+        row,
+    )
+    .expect("Index should be valid since model and ListBox are related");
+
+    // e.g. a pinned recipient that hasn't been discovered
+    if model_item.endpoint_info().present.is_none() {
+        return;
+    }
 
     emit_send_files(win, &model_item);
 
@@ -71,27 +158,309 @@ pub fn handle_recipient_card_clicked(
     row.set_activatable(false);
 }
 
-fn emit_send_files(win: &PacketApplicationWindow, model_item: &SendRequestState) {
+/// Focuses the card of an available recipient going by the name, returning
+/// whether there was one.
+pub fn focus_recipient_card(win: &PacketApplicationWindow, device_name: &str) -> bool {
     let imp = win.imp();
 
-    let endpoint_info = model_item.endpoint_info();
-    let files_to_send = model_item.imp().files.borrow().clone();
+    let Some(model_item) = imp
+        .recipient_model
+        .iter::<SendRequestState>()
+        .filter_map(|it| it.ok())
+        .find(|it| {
+            let endpoint_info = it.endpoint_info();
+            endpoint_info.present.is_some() && endpoint_info.name.as_deref() == Some(device_name)
+        })
+    else {
+        return false;
+    };
 
-    // Only one transfer at a time is supported by the protocol
-    // Whether it be receiving or sending
-    let will_be_queued = imp
+    get_listbox_row_from_model_item::<SendRequestState>(
+        &imp.recipient_sorted_model,
+        &imp.recipient_listbox,
+        &model_item,
+    )
+    .is_some_and(|row| row.grab_focus())
+}
+
+/// Re-sends to every recipient whose transfer failed.
+///
+/// All but the first are queued right away, since the first one will be the
+/// active transfer by the time the rest get processed.
+pub fn retry_failed_sends(win: &PacketApplicationWindow) {
+    let imp = win.imp();
+
+    let failed_model_items = imp
         .recipient_model
         .iter::<SendRequestState>()
         .filter_map(|it| it.ok())
-        .find(|it| match it.transfer_state() {
-            TransferState::RequestedForConsent | TransferState::OngoingTransfer => true,
-            _ => false,
+        .filter(|it| {
+            it.transfer_state() == TransferState::Failed && it.endpoint_info().present.is_some()
         })
-        .is_some();
-    if will_be_queued {
+        .collect::<Vec<_>>();
+
+    for (idx, model_item) in failed_model_items.iter().enumerate() {
+        if idx > 0 {
+            model_item.set_transfer_state(TransferState::Queued);
+        }
+        emit_send_files(win, model_item);
+    }
+}
+
+/// Recipients that are around and not part of a transfer yet.
+pub fn idle_recipients(win: &PacketApplicationWindow) -> Vec<SendRequestState> {
+    win.imp()
+        .recipient_model
+        .iter::<SendRequestState>()
+        .filter_map(|it| it.ok())
+        .filter(|it| {
+            it.transfer_state() == TransferState::AwaitingConsentOrIdle
+                && it.endpoint_info().present.is_some()
+        })
+        .collect()
+}
+
+/// Sends to every idle recipient, one after another through the queue like
+/// [`retry_failed_sends`].
+pub fn send_to_all_recipients(win: &PacketApplicationWindow) {
+    let imp = win.imp();
+
+    let model_items = idle_recipients(win);
+    tracing::info!(count = model_items.len(), "Sending to all recipients");
+
+    for (idx, model_item) in model_items.iter().enumerate() {
+        if idx > 0 {
+            model_item.set_transfer_state(TransferState::Queued);
+        }
+        emit_send_files(win, model_item);
+
+        // Same as with clicking on the card, only reset this on Cancelled
+        if let Some(row) = get_listbox_row_from_model_item::<SendRequestState>(
+            &imp.recipient_sorted_model,
+            &imp.recipient_listbox,
+            model_item,
+        ) {
+            row.set_activatable(false);
+        }
+    }
+
+    imp.send_all_recipients.replace(model_items);
+    update_recipients_header_buttons(win);
+}
+
+/// Shows how many of the recipients of "Send to All" were sent to so far.
+fn update_send_all_progress(win: &PacketApplicationWindow) {
+    let imp = win.imp();
+
+    let send_all_recipients = imp.send_all_recipients.borrow();
+    let total = send_all_recipients.len();
+    let (mut done_count, mut failed_count) = (0, 0);
+    for model_item in send_all_recipients.iter() {
+        match model_item.transfer_state() {
+            TransferState::Done => done_count += 1,
+            TransferState::Failed => failed_count += 1,
+            _ => {}
+        }
+    }
+
+    let mut label = formatx!(
+        ngettext(
+            "Sent to {} of {} device",
+            "Sent to {} of {} devices",
+            total as u32
+        ),
+        done_count,
+        total
+    )
+    .unwrap_or_else(|_| "badly formatted locale string".into());
+    if failed_count > 0 {
+        label.push_str(", ");
+        label.push_str(
+            &formatx!(
+                ngettext("{} failed", "{} failed", failed_count as u32),
+                failed_count
+            )
+            .unwrap_or_else(|_| "badly formatted locale string".into()),
+        );
+    }
+
+    imp.select_recipient_send_all_progress_label
+        .set_label(&label);
+    imp.select_recipient_send_all_progress_label
+        .set_visible(total > 0);
+}
+
+/// Shows the "Retry Failed" and "Clear Completed" buttons only when there's
+/// something for them to act on.
+pub fn update_recipients_header_buttons(win: &PacketApplicationWindow) {
+    let imp = win.imp();
+
+    let (mut has_failed_transfers, mut has_completed_transfers) = (false, false);
+    for model_item in imp
+        .recipient_model
+        .iter::<SendRequestState>()
+        .filter_map(|it| it.ok())
+    {
+        match model_item.transfer_state() {
+            TransferState::Failed => {
+                has_failed_transfers = true;
+                has_completed_transfers = true;
+            }
+            TransferState::Done => has_completed_transfers = true,
+            _ => {}
+        }
+    }
+
+    imp.select_recipient_retry_failed_button
+        .set_visible(has_failed_transfers);
+    imp.select_recipient_clear_completed_button
+        .set_visible(has_completed_transfers);
+    // Not worth it for a single recipient, that's just clicking on the card
+    imp.select_recipient_send_all_button
+        .set_visible(idle_recipients(win).len() > 1);
+
+    update_send_all_progress(win);
+}
+
+/// Adds a card for a device that was entered by its address rather than
+/// discovered, and sends the files to it right away.
+pub fn send_to_manual_recipient(win: &PacketApplicationWindow, addr: std::net::SocketAddrV4) {
+    let imp = win.imp();
+
+    let endpoint_info = objects::EndpointInfo::manual(addr);
+    let existing_model_item = imp
+        .send_transfers_id_cache
+        .blocking_lock()
+        .get(&endpoint_info.id)
+        .cloned();
+
+    let model_item = match existing_model_item {
+        Some(model_item) => {
+            if matches!(
+                model_item.transfer_state(),
+                TransferState::Queued
+                    | TransferState::RequestedForConsent
+                    | TransferState::OngoingTransfer
+            ) {
+                return;
+            }
+
+            model_item
+        }
+        None => {
+            tracing::info!(%endpoint_info, "Added manual endpoint");
+
+            let model_item = SendRequestState::new();
+            let id = endpoint_info.id.clone();
+            model_item.set_endpoint_info(endpoint_info);
+            imp.recipient_model.insert(0, &model_item);
+            imp.send_transfers_id_cache
+                .blocking_lock()
+                .insert(id, model_item.clone());
+
+            model_item
+        }
+    };
+
+    emit_send_files(win, &model_item);
+
+    // Same as with clicking on the card, only reset this on Cancelled
+    if let Some(row) = get_listbox_row_from_model_item::<SendRequestState>(
+        &imp.recipient_sorted_model,
+        &imp.recipient_listbox,
+        &model_item,
+    ) {
+        row.set_activatable(false);
+    }
+}
+
+/// Resolves to whether the recipient is around, waiting up to `timeout` for it
+/// to be rediscovered. Discovered endpoints update the card with the same id.
+async fn wait_for_recipient(model_item: &SendRequestState, timeout: Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        if model_item.endpoint_info().present.is_some() {
+            return true;
+        }
+
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+
+        futures_timer::Delay::new(Duration::from_millis(500)).await;
+    }
+}
+
+fn is_send_active(win: &PacketApplicationWindow) -> bool {
+    win.imp()
+        .recipient_model
+        .iter::<SendRequestState>()
+        .filter_map(|it| it.ok())
+        .any(|it| {
+            matches!(
+                it.transfer_state(),
+                TransferState::RequestedForConsent | TransferState::OngoingTransfer
+            )
+        })
+}
+
+/// Only one transfer at a time is supported by the protocol, whether it be
+/// receiving or sending. So the sends are queued up on our side, which unlike
+/// handing them all to rqs_lib at once, allows cancelling them before they start.
+fn emit_send_files(win: &PacketApplicationWindow, model_item: &SendRequestState) {
+    let imp = win.imp();
+
+    // Forcing the queue with the state is for when several sends are emitted
+    // at once, since the first one isn't active until rqs_lib reports back
+    if is_send_active(win) || model_item.transfer_state() == TransferState::Queued {
         model_item.set_transfer_state(TransferState::Queued);
+        imp.send_queue.borrow_mut().push_back(model_item.clone());
+        update_send_queue_positions(win);
+        return;
     }
 
+    send_files_to_rqs(win, model_item);
+}
+
+/// Starts the next queued send, unless there's still a transfer going on.
+fn dispatch_next_queued_send(win: &PacketApplicationWindow) {
+    if is_send_active(win) {
+        return;
+    }
+
+    let Some(model_item) = win.imp().send_queue.borrow_mut().pop_front() else {
+        return;
+    };
+    update_send_queue_positions(win);
+    model_item.set_queue_position(0);
+
+    send_files_to_rqs(win, &model_item);
+}
+
+/// Takes a send out of the queue before it gets started.
+fn cancel_queued_send(win: &PacketApplicationWindow, model_item: &SendRequestState) {
+    win.imp()
+        .send_queue
+        .borrow_mut()
+        .retain(|it| it != model_item);
+    model_item.set_queue_position(0);
+    update_send_queue_positions(win);
+
+    model_item.set_transfer_state(TransferState::AwaitingConsentOrIdle);
+}
+
+fn update_send_queue_positions(win: &PacketApplicationWindow) {
+    for (idx, model_item) in win.imp().send_queue.borrow().iter().enumerate() {
+        model_item.set_queue_position(idx as u32 + 1);
+    }
+}
+
+fn send_files_to_rqs(win: &PacketApplicationWindow, model_item: &SendRequestState) {
+    let imp = win.imp();
+
+    let endpoint_info = model_item.endpoint_info();
+    let files_to_send = model_item.imp().files.borrow().clone();
+
     tokio_runtime().spawn(clone!(
         #[weak(rename_to = file_sender)]
         imp.file_sender,
@@ -124,6 +493,82 @@ fn emit_send_files(win: &PacketApplicationWindow, model_item: &SendRequestState)
     ));
 }
 
+/// Why a send got cancelled on our side, each with its own token to disarm it
+/// and flag to tell the failure apart by.
+#[derive(Debug, Clone, Copy)]
+enum SendCancelReason {
+    NoResponse,
+    ConnectionLost,
+}
+
+impl SendCancelReason {
+    fn ctk(self, model_item: &SendRequestState) -> &RefCell<Option<CancellationToken>> {
+        match self {
+            Self::NoResponse => &model_item.imp().send_start_timeout_ctk,
+            Self::ConnectionLost => &model_item.imp().connection_loss_ctk,
+        }
+    }
+
+    fn flag(self, model_item: &SendRequestState) -> &Cell<bool> {
+        match self {
+            Self::NoResponse => &model_item.imp().is_timed_out,
+            Self::ConnectionLost => &model_item.imp().is_connection_lost,
+        }
+    }
+
+    fn applies_to(self, state: TransferState) -> bool {
+        match self {
+            Self::NoResponse => state == TransferState::RequestedForConsent,
+            Self::ConnectionLost => matches!(
+                state,
+                TransferState::RequestedForConsent | TransferState::OngoingTransfer
+            ),
+        }
+    }
+}
+
+/// Cancels the send once `trigger` resolves, unless the transfer settles first.
+/// Does nothing if one is already armed for the same `reason`.
+fn cancel_send_on(
+    win: &PacketApplicationWindow,
+    model_item: &SendRequestState,
+    id: String,
+    reason: SendCancelReason,
+    trigger: impl Future<Output = ()> + 'static,
+) {
+    if reason.ctk(model_item).borrow().is_some() {
+        return;
+    }
+
+    let ctk = CancellationToken::new();
+    reason.ctk(model_item).replace(Some(ctk.clone()));
+    reason.flag(model_item).set(false);
+
+    glib::spawn_future_local(clone!(
+        #[weak]
+        win,
+        #[weak]
+        model_item,
+        async move {
+            tokio::select! {
+                _ = trigger => {}
+                _ = ctk.cancelled() => {
+                    return;
+                }
+            };
+
+            reason.ctk(&model_item).take();
+            if !reason.applies_to(model_item.transfer_state()) {
+                return;
+            }
+
+            tracing::warn!(%id, ?reason, "Cancelling send");
+            reason.flag(&model_item).set(true);
+            win.send_transfer_cancel(id).await;
+        }
+    ));
+}
+
 pub fn create_recipient_card(
     win: &PacketApplicationWindow,
     _model: &gio::ListStore,
@@ -170,9 +615,7 @@ pub fn create_recipient_card(
                 .map(|it| it.size() as usize)
                 .fold(0, |acc, x| acc + x);
 
-            eta_estimator
-                .borrow_mut()
-                .prepare_for_new_transfer(Some(total_size));
+            *eta_estimator.borrow_mut() = win.new_eta_estimator(total_size);
         }
     }
 
@@ -191,6 +634,7 @@ pub fn create_recipient_card(
     let device_avatar = adw::Avatar::builder().show_initials(true).size(48).build();
     model_item
         .bind_property("device-name", &device_avatar, "text")
+        .transform_to(|_, name: String| Some(utils::sanitize_device_name(&name)))
         .sync_create()
         .build();
     root_box.append(&device_avatar);
@@ -214,6 +658,7 @@ pub fn create_recipient_card(
         .build();
     model_item
         .bind_property("device-name", &title_label, "label")
+        .transform_to(|_, name: String| Some(utils::sanitize_device_name(&name)))
         .sync_create()
         .build();
     let result_label = gtk::Label::builder()
@@ -225,31 +670,102 @@ pub fn create_recipient_card(
         .halign(gtk::Align::Start)
         .wrap(true)
         .label(&gettext("Unavailable"))
-        .visible(false)
+        .visible(
+            model_item.endpoint_info().present.is_none()
+                && model_item.transfer_state() == TransferState::AwaitingConsentOrIdle,
+        )
         .css_classes(["dimmed"])
         .build();
     let pincode_label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .selectable(true)
+        .visible(false)
+        .css_classes(["monospace", "heading"])
+        .build();
+    let pincode_copy_button = create_pincode_copy_button(clone!(
+        #[weak]
+        model_item,
+        #[upgrade_or]
+        None,
+        move || {
+            model_item
+                .event()?
+                .msg
+                .as_client_unchecked()
+                .metadata
+                .as_ref()?
+                .pin_code
+                .clone()
+        }
+    ));
+    pincode_label
+        .bind_property("visible", &pincode_copy_button, "visible")
+        .sync_create()
+        .build();
+    let pincode_box = gtk::Box::builder().spacing(6).build();
+    pincode_box.append(&pincode_label);
+    pincode_box.append(&pincode_copy_button);
+    let history_label = gtk::Label::builder()
         .halign(gtk::Align::Start)
         .wrap(true)
         .visible(false)
-        .css_classes(["dimmed", "monospace"])
+        .css_classes(["dimmed", "caption"])
+        .build();
+    let manual_label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .wrap(true)
+        .label(&gettext("Manual"))
+        .visible(model_item.endpoint_info().is_manual())
+        .css_classes(["dimmed", "caption"])
         .build();
     main_box.append(&title_label);
+    main_box.append(&manual_label);
     main_box.append(&result_label);
     main_box.append(&unavailibility_label);
-    main_box.append(&pincode_label);
+    main_box.append(&pincode_box);
+    main_box.append(&history_label);
+
+    let update_history_label = clone!(
+        #[weak]
+        win,
+        #[weak]
+        model_item,
+        #[weak]
+        history_label,
+        move || {
+            let entry = win
+                .recipient_history()
+                .get(&model_item.device_name())
+                .copied();
+            let is_idle = matches!(
+                model_item.transfer_state(),
+                TransferState::AwaitingConsentOrIdle | TransferState::Done
+            );
+
+            match entry {
+                Some(entry) if is_idle => {
+                    history_label.set_label(&format_recipient_history(&entry));
+                    history_label.set_visible(true);
+                }
+                _ => history_label.set_visible(false),
+            }
+        }
+    );
+    update_history_label();
+    model_item.connect_device_name_notify(clone!(
+        #[strong]
+        update_history_label,
+        move |_| update_history_label()
+    ));
 
     model_item.connect_transfer_state_notify(clone!(
         #[weak]
         imp,
-        #[weak]
-        result_label,
+        #[strong]
+        update_history_label,
         move |model_item| {
-            if model_item.transfer_state() == TransferState::Queued {
-                result_label.set_visible(true);
-                result_label.set_label(&gettext("Queued"));
-                result_label.set_css_classes(&[]);
-            };
+            update_history_label();
 
             // Prevent exiting the recipients view until all transfers
             // are settled
@@ -271,11 +787,24 @@ pub fn create_recipient_card(
             } else {
                 imp.select_recipients_dialog.set_can_close(true);
             }
+
+            update_recipients_header_buttons(&imp.obj());
         }
     ));
 
-    let progress_bar = gtk::ProgressBar::builder().visible(false).build();
-    main_box.append(&progress_bar);
+    let progress_box = gtk::Box::builder().spacing(8).build();
+    main_box.append(&progress_box);
+    let progress_bar = gtk::ProgressBar::builder()
+        .visible(false)
+        .hexpand(true)
+        .valign(gtk::Align::Center)
+        .build();
+    progress_box.append(&progress_bar);
+    let percentage_label = gtk::Label::builder()
+        .visible(false)
+        .css_classes(["caption", "numeric"])
+        .build();
+    progress_box.append(&percentage_label);
 
     let eta_label = gtk::Label::builder()
         .halign(gtk::Align::Start)
@@ -289,6 +818,109 @@ pub fn create_recipient_card(
 
     root_box.append(&adw::Bin::builder().hexpand(true).build());
 
+    let is_pinned = win.is_pinned_recipient(&model_item.device_name());
+    let pin_button = gtk::ToggleButton::builder()
+        .valign(gtk::Align::Center)
+        .halign(gtk::Align::Center)
+        .icon_name(if is_pinned {
+            "starred-symbolic"
+        } else {
+            "non-starred-symbolic"
+        })
+        .css_classes(["circular", "flat"])
+        .tooltip_text(&if is_pinned {
+            gettext("Unpin")
+        } else {
+            gettext("Pin")
+        })
+        .active(is_pinned)
+        // Manual recipients are found by their address, not their name
+        .visible(!model_item.endpoint_info().is_manual())
+        .build();
+    pin_button.connect_toggled(clone!(
+        #[weak]
+        win,
+        #[weak]
+        model_item,
+        move |button| {
+            let is_pinned = button.is_active();
+            if is_pinned {
+                button.set_icon_name("starred-symbolic");
+                button.set_tooltip_text(Some(&gettext("Unpin")));
+            } else {
+                button.set_icon_name("non-starred-symbolic");
+                button.set_tooltip_text(Some(&gettext("Pin")));
+            }
+
+            win.set_recipient_pinned(&model_item.device_name(), is_pinned);
+        }
+    ));
+    root_box.append(&pin_button);
+
+    // For diagnosing devices that show up but can't be connected to
+    let endpoint_info_grid = gtk::Grid::builder()
+        .row_spacing(6)
+        .column_spacing(12)
+        .margin_start(6)
+        .margin_end(6)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+    let endpoint_info_popover = gtk::Popover::builder().child(&endpoint_info_grid).build();
+    endpoint_info_popover.connect_show(clone!(
+        #[weak]
+        model_item,
+        #[weak]
+        endpoint_info_grid,
+        move |_| {
+            while let Some(child) = endpoint_info_grid.first_child() {
+                endpoint_info_grid.remove(&child);
+            }
+
+            let endpoint_info = model_item.endpoint_info();
+            let not_available = || gettext("N/A");
+            let rows = [
+                (gettext("Name"), endpoint_info.name.clone()),
+                (gettext("ID"), Some(endpoint_info.id.clone())),
+                (gettext("IP Address"), endpoint_info.ip.clone()),
+                (gettext("Port"), endpoint_info.port.clone()),
+                (
+                    gettext("Present"),
+                    endpoint_info
+                        .present
+                        .map(|it| if it { gettext("Yes") } else { gettext("No") }),
+                ),
+            ];
+            for (row_idx, (key, value)) in rows.into_iter().enumerate() {
+                let key_label = gtk::Label::builder()
+                    .label(&key)
+                    .xalign(0.)
+                    .css_classes(["dimmed"])
+                    .build();
+                let value_label = gtk::Label::builder()
+                    .label(&value.unwrap_or_else(not_available))
+                    .xalign(0.)
+                    .selectable(true)
+                    .wrap(true)
+                    .wrap_mode(gtk::pango::WrapMode::Char)
+                    .max_width_chars(32)
+                    .css_classes(["monospace"])
+                    .build();
+                endpoint_info_grid.attach(&key_label, 0, row_idx as i32, 1, 1);
+                endpoint_info_grid.attach(&value_label, 1, row_idx as i32, 1, 1);
+            }
+        }
+    ));
+    let endpoint_info_button = gtk::MenuButton::builder()
+        .valign(gtk::Align::Center)
+        .halign(gtk::Align::Center)
+        .icon_name("help-about-symbolic")
+        .css_classes(["circular", "flat"])
+        .tooltip_text(&gettext("Device Info"))
+        .popover(&endpoint_info_popover)
+        .build();
+    root_box.append(&endpoint_info_button);
+
     let retry_button = gtk::Button::builder()
         .valign(gtk::Align::Center)
         .halign(gtk::Align::Center)
@@ -303,8 +935,45 @@ pub fn create_recipient_card(
         imp,
         #[weak]
         model_item,
-        move |_button| {
-            emit_send_files(&imp.obj(), &model_item);
+        #[weak]
+        result_label,
+        move |button| {
+            let endpoint_info = model_item.endpoint_info();
+            if endpoint_info.present.is_some() || endpoint_info.is_manual() {
+                emit_send_files(&imp.obj(), &model_item);
+                return;
+            }
+
+            // The device left since, so the address it had can't be relied on
+            tracing::info!(id = %endpoint_info.id, "Rediscovering recipient to retry");
+            button.set_sensitive(false);
+            result_label.set_label(&gettext("Looking for device…"));
+            result_label.set_css_classes(&["dimmed"]);
+            imp.obj().start_mdns_discovery(Some(true));
+
+            glib::spawn_future_local(clone!(
+                #[weak]
+                imp,
+                #[weak]
+                model_item,
+                #[weak]
+                button,
+                #[weak]
+                result_label,
+                async move {
+                    if wait_for_recipient(&model_item, RETRY_DISCOVERY_TIMEOUT).await {
+                        emit_send_files(&imp.obj(), &model_item);
+                    } else {
+                        tracing::info!(
+                            id = %model_item.endpoint_info().id,
+                            "Recipient didn't show up again"
+                        );
+                        result_label.set_label(&gettext("Device unavailable"));
+                        result_label.set_css_classes(&["error"]);
+                    }
+                    button.set_sensitive(true);
+                }
+            ));
         }
     ));
 
@@ -318,31 +987,140 @@ pub fn create_recipient_card(
         .build();
     root_box.append(&cancel_transfer_button);
 
+    let dismiss_button = gtk::Button::builder()
+        .valign(gtk::Align::Center)
+        .halign(gtk::Align::Center)
+        .icon_name("cross-large-symbolic")
+        .css_classes(["circular", "flat"])
+        .tooltip_text(&gettext("Remove From List"))
+        .visible(model_item.transfer_state() == TransferState::AwaitingConsentOrIdle)
+        .build();
+    root_box.append(&dismiss_button);
+    dismiss_button.connect_clicked(clone!(
+        #[weak]
+        win,
+        #[weak]
+        model_item,
+        move |_| {
+            dismiss_recipient(&win, &model_item);
+        }
+    ));
+    model_item.connect_transfer_state_notify(clone!(
+        #[weak]
+        dismiss_button,
+        move |model_item| {
+            dismiss_button
+                .set_visible(model_item.transfer_state() == TransferState::AwaitingConsentOrIdle);
+        }
+    ));
+
+    let update_queued_ui = clone!(
+        #[weak]
+        model_item,
+        #[weak]
+        result_label,
+        #[weak]
+        cancel_transfer_button,
+        move || {
+            // Taking it out of the queue leaves the active transfer alone
+            let is_queued = model_item.transfer_state() == TransferState::Queued;
+            cancel_transfer_button.set_tooltip_text(Some(&if is_queued {
+                gettext("Remove From Queue")
+            } else {
+                gettext("Cancel")
+            }));
+            if !is_queued {
+                return;
+            }
+
+            result_label.set_visible(true);
+            result_label.set_css_classes(&[]);
+            match model_item.queue_position() {
+                0 => result_label.set_label(&gettext("Queued")),
+                pos => result_label.set_label(
+                    &formatx!(gettext("Queued, #{}"), pos)
+                        .unwrap_or_else(|_| "badly formatted locale string".into()),
+                ),
+            }
+
+            // Once out of the queue it's up to rqs_lib, until it reports back
+            cancel_transfer_button.set_sensitive(true);
+            cancel_transfer_button.set_visible(model_item.queue_position() > 0);
+        }
+    );
+    update_queued_ui();
+    model_item.connect_transfer_state_notify(clone!(
+        #[strong]
+        update_queued_ui,
+        move |_| update_queued_ui()
+    ));
+    model_item.connect_queue_position_notify(clone!(
+        #[strong]
+        update_queued_ui,
+        move |_| update_queued_ui()
+    ));
+
     cancel_transfer_button.connect_clicked(clone!(
-        #[weak(rename_to = rqs)]
-        imp.rqs,
+        #[weak]
+        win,
+        #[weak]
+        model_item,
+        #[weak]
+        result_label,
         #[strong]
         id,
-        move |_button| {
-            let mut guard = rqs.blocking_lock();
-            if let Some(rqs) = guard.as_mut() {
-                _ = rqs
-                    .message_sender
-                    .send(ChannelMessage {
-                        id: id.clone(),
-                        msg: rqs_lib::channel::Message::Lib {
-                            action: rqs_lib::channel::TransferAction::TransferCancel,
-                        },
-                    })
-                    .inspect_err(|err| tracing::error!(%err));
+        move |button| {
+            if model_item.transfer_state() == TransferState::Queued
+                && model_item.queue_position() > 0
+            {
+                tracing::info!(%id, "Cancelled queued send");
+                cancel_queued_send(&win, &model_item);
+
+                button.set_visible(false);
+                result_label.set_visible(false);
+                if let Some(row) = get_listbox_row_from_model_item::<SendRequestState>(
+                    &win.imp().recipient_sorted_model,
+                    &win.imp().recipient_listbox,
+                    &model_item,
+                ) {
+                    row.set_activatable(model_item.endpoint_info().present.is_some());
+                }
+
+                return;
             }
+
+            glib::spawn_future_local(clone!(
+                #[weak]
+                win,
+                #[strong]
+                id,
+                async move {
+                    win.send_transfer_cancel(id).await;
+                }
+            ));
         }
     ));
 
-    fn set_progress_bar_fraction(progress_bar: &gtk::ProgressBar, client_msg: &MessageClient) {
+    fn set_progress_bar_fraction(
+        progress_bar: &gtk::ProgressBar,
+        percentage_label: &gtk::Label,
+        client_msg: &MessageClient,
+    ) {
         if let Some(metadata) = &client_msg.metadata {
             if metadata.total_bytes > 0 {
-                progress_bar.set_fraction(metadata.ack_bytes as f64 / metadata.total_bytes as f64);
+                let fraction = metadata.ack_bytes as f64 / metadata.total_bytes as f64;
+                progress_bar.set_fraction(fraction);
+                percentage_label.set_label(
+                    &formatx!(
+                        // Translators: A progress percentage, e.g. "73%"
+                        gettext("{}%"),
+                        (fraction * 100.0).floor() as u32
+                    )
+                    .unwrap_or_else(|_| "badly formatted locale string".into()),
+                );
+                percentage_label.set_visible(true);
+            } else {
+                percentage_label.set_visible(false);
             }
         }
     }
@@ -365,14 +1143,12 @@ pub fn create_recipient_card(
         #[weak]
         win,
         #[weak]
-        retry_button,
-        #[weak]
         unavailibility_label,
         move |model_item| {
             let imp = win.imp();
             let is_idle_card = model_item.transfer_state() == TransferState::AwaitingConsentOrIdle;
             if let Some(row) = get_listbox_row_from_model_item::<SendRequestState>(
-                &imp.recipient_model,
+                &imp.recipient_sorted_model,
                 &imp.recipient_listbox,
                 model_item,
             ) {
@@ -380,11 +1156,10 @@ pub fn create_recipient_card(
             };
 
             let endpoint_info = model_item.endpoint_info();
+            // The retry button stays usable, it rediscovers the device first
             if endpoint_info.present.is_none() {
-                retry_button.set_sensitive(false);
                 unavailibility_label.set_visible(is_idle_card);
             } else {
-                retry_button.set_sensitive(true);
                 unavailibility_label.set_visible(false);
 
                 // Update device name on re-connection
@@ -409,6 +1184,35 @@ pub fn create_recipient_card(
                 let client_msg = event_msg.msg.as_client_unchecked();
                 let state = client_msg.state.as_ref().unwrap_or(&RqsState::Initial);
 
+                // The event gets replayed whenever the card is recreated, so the
+                // queue should only move on when the transfer actually settles
+                let was_send_active = matches!(
+                    model_item.transfer_state(),
+                    TransferState::RequestedForConsent | TransferState::OngoingTransfer
+                );
+
+                if matches!(
+                    state,
+                    RqsState::SendingFiles
+                        | RqsState::Finished
+                        | RqsState::Cancelled
+                        | RqsState::Disconnected
+                        | RqsState::Rejected
+                ) && let Some(ctk) = model_item.imp().send_start_timeout_ctk.take()
+                {
+                    ctk.cancel();
+                }
+                if matches!(
+                    state,
+                    RqsState::Finished
+                        | RqsState::Cancelled
+                        | RqsState::Disconnected
+                        | RqsState::Rejected
+                ) && let Some(ctk) = model_item.imp().connection_loss_ctk.take()
+                {
+                    ctk.cancel();
+                }
+
                 match state {
                     RqsState::Initial => {}
                     RqsState::ReceivedConnectionRequest => {}
@@ -426,7 +1230,7 @@ pub fn create_recipient_card(
                         model_item.set_transfer_state(TransferState::RequestedForConsent);
 
                         let listbox_row = get_listbox_row_from_model_item::<SendRequestState>(
-                            &imp.recipient_model,
+                            &imp.recipient_sorted_model,
                             &imp.recipient_listbox,
                             model_item,
                         );
@@ -457,6 +1261,26 @@ pub fn create_recipient_card(
                         );
 
                         eta_estimator.borrow_mut().prepare_for_new_transfer(None);
+
+                        // The receiver might've gone away without a word, in which
+                        // case rqs_lib never reports anything back
+                        cancel_send_on(
+                            &imp.obj(),
+                            &model_item,
+                            event_msg.id.to_string(),
+                            SendCancelReason::NoResponse,
+                            futures_timer::Delay::new(SEND_START_TIMEOUT),
+                        );
+
+                        // Left to itself, a transfer whose connection dropped
+                        // only fails once rqs_lib times out on it
+                        cancel_send_on(
+                            &imp.obj(),
+                            &model_item,
+                            event_msg.id.to_string(),
+                            SendCancelReason::ConnectionLost,
+                            monitors::wait_for_network_loss(imp.obj().subscribe_connectivity()),
+                        );
                     }
                     RqsState::SendingFiles => {
                         model_item.set_transfer_state(TransferState::OngoingTransfer);
@@ -484,15 +1308,23 @@ pub fn create_recipient_card(
                         eta_label.set_label(&eta_text);
 
                         progress_bar.set_visible(true);
-                        set_progress_bar_fraction(&progress_bar, &client_msg);
+                        set_progress_bar_fraction(&progress_bar, &percentage_label, &client_msg);
                     }
-                    RqsState::Disconnected => {
+                    // A request that went unanswered, or a transfer whose
+                    // connection dropped, is cancelled by us, but it's still a
+                    // failure as far as the user is concerned
+                    RqsState::Disconnected | RqsState::Cancelled
+                        if matches!(state, RqsState::Disconnected)
+                            || model_item.imp().is_timed_out.get()
+                            || model_item.imp().is_connection_lost.get() =>
+                    {
                         model_item.set_transfer_state(TransferState::Failed);
-                        // FIXME: Wait for 5~10 seconds after a send and timeout
-                        // if did not receive SendingFiles within that timeframe
-                        // This is how google does it in their client
+                        if was_send_active {
+                            dispatch_next_queued_send(&imp.obj());
+                        }
 
                         progress_bar.set_visible(false);
+                        percentage_label.set_visible(false);
                         cancel_transfer_button.set_visible(false);
                         eta_label.set_visible(false);
                         unavailibility_label.set_visible(false);
@@ -501,25 +1333,38 @@ pub fn create_recipient_card(
                         retry_button.set_visible(true);
 
                         result_label.set_visible(true);
-                        result_label.set_label(&gettext("Failed"));
+                        if model_item.imp().is_timed_out.replace(false) {
+                            result_label.set_label(&gettext("No response"));
+                        } else if model_item.imp().is_connection_lost.replace(false) {
+                            result_label.set_label(&gettext("Connection lost"));
+                        } else {
+                            result_label.set_label(&gettext("Failed"));
+                        }
                         result_label.set_css_classes(&["error"]);
                     }
                     RqsState::Rejected => {
                         model_item.set_transfer_state(TransferState::Failed);
+                        if was_send_active {
+                            dispatch_next_queued_send(&imp.obj());
+                        }
                         // Outbound(Reject) is not handled on lib side
                         // rqs_lib::hdl::outbound: Cannot process: consent denied: Reject
                     }
                     RqsState::Cancelled => {
                         model_item.set_transfer_state(TransferState::AwaitingConsentOrIdle);
+                        if was_send_active {
+                            dispatch_next_queued_send(&imp.obj());
+                        }
 
                         let listbox_row = get_listbox_row_from_model_item::<SendRequestState>(
-                            &imp.recipient_model,
+                            &imp.recipient_sorted_model,
                             &imp.recipient_listbox,
                             model_item,
                         );
                         set_row_activatable(model_item, listbox_row.as_ref(), true);
 
                         progress_bar.set_visible(false);
+                        percentage_label.set_visible(false);
                         cancel_transfer_button.set_visible(false);
                         eta_label.set_visible(false);
                         result_label.set_visible(false);
@@ -532,10 +1377,19 @@ pub fn create_recipient_card(
                         model_item.set_event(None::<objects::ChannelMessage>);
                     }
                     RqsState::Finished => {
+                        // The event gets replayed whenever the card is recreated
+                        if model_item.transfer_state() != TransferState::Done {
+                            imp.obj()
+                                .record_sent_to_recipient(&model_item.device_name());
+                        }
                         model_item.set_transfer_state(TransferState::Done);
+                        if was_send_active {
+                            dispatch_next_queued_send(&imp.obj());
+                        }
 
                         cancel_transfer_button.set_visible(false);
                         progress_bar.set_visible(false);
+                        percentage_label.set_visible(false);
                         eta_label.set_visible(false);
                         retry_button.set_visible(false);
                         unavailibility_label.set_visible(false);
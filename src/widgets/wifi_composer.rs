@@ -0,0 +1,306 @@
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use anyhow::Context;
+use gettextrs::gettext;
+use gtk::{
+    gio,
+    glib::{self, clone},
+};
+
+use crate::{tokio_runtime, widgets::display_wifi_security_type, window::PacketApplicationWindow};
+
+/// Going by the variant names of the protocol's `WifiCredentialsMetadata.SecurityType`,
+/// same as the ones received.
+const WIFI_SECURITY_TYPES: [&str; 3] = ["WpaPsk", "Wep", "Open"];
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|it| it.is_ascii_hexdigit())
+}
+
+fn is_valid_wifi_credentials(ssid: &str, password: &str, security_type: &str) -> bool {
+    if ssid.is_empty() || ssid.len() > 32 {
+        return false;
+    }
+
+    match security_type {
+        "Open" => true,
+        // A passphrase, or the raw key in hex
+        "WpaPsk" => {
+            (8..=63).contains(&password.len()) || (password.len() == 64 && is_hex(password))
+        }
+        // 64/128-bit keys, either as ASCII or hex
+        "Wep" => match password.len() {
+            5 | 13 => password.is_ascii(),
+            10 | 26 => is_hex(password),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Escapes the characters that are special in the `WIFI:` text format.
+fn escape_wifi_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for it in value.chars() {
+        if matches!(it, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(it);
+    }
+    escaped
+}
+
+/// The `WIFI:T:...;S:...;P:...;;` text that Wi-Fi QR codes use, which
+/// most phones know how to join a network from.
+fn format_wifi_credentials(ssid: &str, password: &str, security_type: &str) -> String {
+    let ssid = escape_wifi_field(ssid);
+    match security_type {
+        "WpaPsk" => format!("WIFI:T:WPA;S:{ssid};P:{};;", escape_wifi_field(password)),
+        "Wep" => format!("WIFI:T:WEP;S:{ssid};P:{};;", escape_wifi_field(password)),
+        _ => format!("WIFI:T:nopass;S:{ssid};;"),
+    }
+}
+
+/// A Wi-Fi credentials file waiting to be sent, in a folder only the user can
+/// read. Both are removed once this is dropped.
+#[derive(Debug)]
+pub struct WifiCredentialsFile {
+    folder: PathBuf,
+    path: PathBuf,
+}
+
+impl WifiCredentialsFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for WifiCredentialsFile {
+    fn drop(&mut self) {
+        tracing::debug!(folder = ?self.folder, "Removing the Wi-Fi credentials file");
+        _ = fs_err::remove_dir_all(&self.folder).inspect_err(|err| tracing::warn!(%err));
+    }
+}
+
+/// rqs_lib can only send files, so the credentials go out as a text file.
+///
+/// Blocking.
+fn write_wifi_credentials_file(
+    ssid: &str,
+    password: &str,
+    security_type: &str,
+) -> anyhow::Result<WifiCredentialsFile> {
+    // A folder of its own, so the file can keep the network's name without
+    // running into an earlier one
+    let folder =
+        glib::user_runtime_dir().join(format!("packet-wifi-{}", glib::uuid_string_random()));
+    let mut dir_builder = std::fs::DirBuilder::new();
+    dir_builder.recursive(true);
+    #[cfg(unix)]
+    dir_builder.mode(0o700);
+    dir_builder
+        .create(&folder)
+        .with_context(|| format!("Failed to create {}", folder.display()))?;
+    // Owns the folder from here on, so it's cleaned up on errors too
+    let mut credentials_file = WifiCredentialsFile {
+        folder,
+        path: PathBuf::new(),
+    };
+
+    // Keep the SSID from being taken as path components
+    let file_stem = ssid
+        .chars()
+        .map(|it| {
+            if std::path::is_separator(it) || it == '\0' {
+                '_'
+            } else {
+                it
+            }
+        })
+        .collect::<String>();
+    let path = credentials_file.folder.join(format!("{file_stem}.txt"));
+    let mut open_options = std::fs::OpenOptions::new();
+    open_options.write(true).create_new(true);
+    #[cfg(unix)]
+    open_options.mode(0o600);
+    open_options
+        .open(&path)
+        .and_then(|mut it| {
+            it.write_all(format_wifi_credentials(ssid, password, security_type).as_bytes())
+        })
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    credentials_file.path = path;
+
+    Ok(credentials_file)
+}
+
+pub fn present_wifi_composer_dialog(win: &PacketApplicationWindow) {
+    let dialog = adw::AlertDialog::builder()
+        .heading(&gettext("Send Wi-Fi Network"))
+        .body(&gettext(
+            "The network details are sent as a text file, which is deleted once the transfer is over",
+        ))
+        .default_response("send")
+        .close_response("cancel")
+        .build();
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("send", &gettext("Send"))]);
+    dialog.set_response_appearance("send", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("send", false);
+
+    let listbox = gtk::ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    dialog.set_extra_child(Some(&listbox));
+
+    let ssid_row = adw::EntryRow::builder()
+        .title(&gettext("Network Name"))
+        .build();
+    listbox.append(&ssid_row);
+
+    let security_model = gtk::StringList::new(&[]);
+    for security_type in WIFI_SECURITY_TYPES {
+        security_model.append(&display_wifi_security_type(security_type));
+    }
+    let security_row = adw::ComboRow::builder()
+        .title(&gettext("Security"))
+        .model(&security_model)
+        .build();
+    listbox.append(&security_row);
+
+    let password_row = adw::PasswordEntryRow::builder()
+        .title(&gettext("Password"))
+        .build();
+    listbox.append(&password_row);
+
+    let selected_security_type = clone!(
+        #[weak]
+        security_row,
+        #[upgrade_or]
+        WIFI_SECURITY_TYPES[0],
+        move || WIFI_SECURITY_TYPES[security_row.selected() as usize]
+    );
+
+    let validate = clone!(
+        #[weak]
+        dialog,
+        #[weak]
+        ssid_row,
+        #[weak]
+        password_row,
+        #[strong]
+        selected_security_type,
+        move || {
+            let security_type = selected_security_type();
+            password_row.set_sensitive(security_type != "Open");
+
+            let is_valid =
+                is_valid_wifi_credentials(&ssid_row.text(), &password_row.text(), security_type);
+            dialog.set_response_enabled("send", is_valid);
+        }
+    );
+    ssid_row.connect_changed(clone!(
+        #[strong]
+        validate,
+        move |_| validate()
+    ));
+    password_row.connect_changed(clone!(
+        #[strong]
+        validate,
+        move |_| validate()
+    ));
+    security_row.connect_selected_notify(clone!(
+        #[strong]
+        validate,
+        move |_| validate()
+    ));
+
+    dialog.connect_response(
+        Some("send"),
+        clone!(
+            #[weak]
+            win,
+            #[weak]
+            ssid_row,
+            #[weak]
+            password_row,
+            move |_, _| {
+                let ssid = ssid_row.text().to_string();
+                let security_type = selected_security_type();
+                let password = match security_type {
+                    "Open" => String::new(),
+                    _ => password_row.text().to_string(),
+                };
+
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    win,
+                    async move {
+                        let credentials_file = tokio_runtime()
+                            .spawn_blocking(move || {
+                                write_wifi_credentials_file(&ssid, &password, security_type)
+                            })
+                            .await
+                            .map_err(|err| anyhow::anyhow!(err))
+                            .and_then(|it| it)
+                            .inspect_err(|err| tracing::error!("{err:#}"));
+
+                        match credentials_file {
+                            Ok(credentials_file) => {
+                                let file = gio::File::for_path(credentials_file.path());
+                                // Held onto until the recipients dialog closes,
+                                // which can only happen once the sends are settled
+                                win.imp()
+                                    .wifi_credentials_files
+                                    .borrow_mut()
+                                    .push(credentials_file);
+                                win.send_files(vec![file]);
+                            }
+                            Err(_) => {
+                                win.imp().toast_overlay.add_toast(adw::Toast::new(&gettext(
+                                    "Couldn't prepare the Wi-Fi network for sending",
+                                )));
+                            }
+                        }
+                    }
+                ));
+            }
+        ),
+    );
+
+    dialog.present(Some(win));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wifi_credentials_format() {
+        assert_eq!(
+            format_wifi_credentials("Home", "hunter22", "WpaPsk"),
+            "WIFI:T:WPA;S:Home;P:hunter22;;"
+        );
+        assert_eq!(
+            format_wifi_credentials("Home", "12345", "Wep"),
+            "WIFI:T:WEP;S:Home;P:12345;;"
+        );
+        assert_eq!(
+            format_wifi_credentials("Cafe", "ignored", "Open"),
+            "WIFI:T:nopass;S:Cafe;;"
+        );
+    }
+
+    #[test]
+    fn wifi_credentials_special_chars_are_escaped() {
+        assert_eq!(
+            format_wifi_credentials(r#"a;b,c:d"e\f"#, "p;w:d", "WpaPsk"),
+            r#"WIFI:T:WPA;S:a\;b\,c\:d\"e\\f;P:p\;w\:d;;"#
+        );
+    }
+}
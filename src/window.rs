@@ -1,5 +1,6 @@
 use std::cell::{Cell, RefCell};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use adw::prelude::*;
@@ -22,9 +23,13 @@ use crate::constants::packet_log_path;
 use crate::ext::MessageExt;
 use crate::objects::{self, SendRequestState};
 use crate::objects::{TransferState, UserAction};
-use crate::plugins::{FileBasedPlugin, NautilusPlugin, Plugin};
-use crate::utils::{strip_user_home_prefix, with_signals_blocked, xdg_download_with_fallback};
-use crate::{monitors, tokio_runtime, widgets};
+use crate::plugins::{
+    DolphinPlugin, FileBasedPlugin, NautilusPlugin, NemoPlugin, Plugin, PluginError, ThunarPlugin,
+};
+use crate::utils::{
+    retry_with_backoff, strip_user_home_prefix, with_signals_blocked, xdg_download_with_fallback,
+};
+use crate::{monitors, tokio_runtime, utils, widgets};
 
 #[derive(Debug)]
 pub enum LoopingTaskHandle {
@@ -40,17 +45,185 @@ pub struct ReceiveTransferCache {
     pub auto_decline_ctk: CancellationToken,
 }
 
+impl ReceiveTransferCache {
+    /// Whether the transfer is over, one way or another.
+    pub fn is_settled(&self) -> bool {
+        use rqs_lib::TransferState;
+
+        self.state.event().is_none_or(|event| {
+            matches!(
+                event.msg.as_client_unchecked().state,
+                Some(
+                    TransferState::Disconnected
+                        | TransferState::Rejected
+                        | TransferState::Cancelled
+                        | TransferState::Finished
+                )
+            )
+        })
+    }
+}
+
+/// In the same order as the options of the accent color combo row.
+const ACCENT_COLORS: [&str; 10] = [
+    "system", "blue", "teal", "green", "yellow", "orange", "red", "pink", "purple", "slate",
+];
+
+/// In the same order as the options of the close action combo row.
+const CLOSE_ACTIONS: [&str; 3] = ["ask", "hide", "quit"];
+
+/// In the same order as the options of the notification level combo row.
+const NOTIFICATION_LEVELS: [&str; 3] = ["all", "completion-only", "none"];
+
+/// In the same order as the options of the receive UI style combo row.
+const RECEIVE_UI_STYLES: [&str; 2] = ["dialog", "toast"];
+
+/// In the same order as the options of the auto open received combo row.
+const AUTO_OPEN_RECEIVED_OPTIONS: [&str; 3] = ["off", "folder", "file"];
+
+const MINI_MODE_WIDTH: i32 = 360;
+const MINI_MODE_HEIGHT: i32 = 320;
+
+const TEMPORARY_VISIBILITY_DURATION: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// How long the offer to send copied files stays up.
+const CLIPBOARD_OFFER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long to keep gathering files to send after a batch comes in, since
+/// file managers tend to launch the app once per selected file.
+const SEND_FILES_COALESCE_WINDOW: std::time::Duration = std::time::Duration::from_millis(500);
+
+const RECENT_RECIPIENTS_MAX: usize = 5;
+/// How long to wait for a previous recipient to show up when sending again.
+const RECENT_RECIPIENT_DISCOVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+const RQS_SETUP_MAX_ATTEMPTS: u32 = 3;
+const RQS_SETUP_INITIAL_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// With `steady-time-estimates`, the speed is a moving average spanning about
+/// this many seconds, rather than a mean over the last 5.
+const STEADY_ETA_WINDOW_SECS: usize = 10;
+
+/// Keys restored by "Reset All Settings", i.e. the preferences. The window
+/// state and such are left alone.
+const RESETTABLE_SETTINGS: &[&str] = &[
+    "device-name",
+    "device-visibility",
+    "trusted-only",
+    "trusted-devices",
+    "accent-color",
+    "blocked-extensions",
+    "auto-decline-blocked-extensions",
+    "pinned-recipients",
+    "download-folder",
+    "text-save-folder",
+    "organize-by-date",
+    "per-device-download-folders",
+    "enable-static-port",
+    "static-port-number",
+    "interface-name",
+    "eager-discovery",
+    "steady-time-estimates",
+    "discovery-idle-timeout",
+    "consent-timeout-seconds",
+    "show-timeout-toast",
+    "auto-response-schedule",
+    "receive-ui-style",
+    "notification-level",
+    "allow-empty-files",
+    "watch-clipboard",
+    "max-files-warn-threshold",
+    "max-size-warn-threshold",
+    "history-retention-days",
+    "short-text-auto-copy",
+    "verify-received-files",
+    "auto-open-received",
+    "receive-only-mode",
+    "close-action",
+    "auto-start",
+    "enable-nautilus-plugin",
+    "enable-dolphin-plugin",
+    "enable-nemo-plugin",
+    "enable-thunar-plugin",
+    "enable-tray-icon",
+];
+
+/// Ports below 1024 are privileged, so only the ones above are allowed.
+fn is_valid_static_port(port_number: i32) -> bool {
+    (1025..=u16::MAX as i32).contains(&port_number)
+}
+
+/// Parses the `ip:port` address of a device entered by hand.
+fn parse_manual_recipient_address(text: &str) -> Option<std::net::SocketAddrV4> {
+    text.trim()
+        .parse::<std::net::SocketAddrV4>()
+        .ok()
+        .filter(|addr| is_valid_static_port(addr.port() as i32))
+}
+
+/// rqs_lib hands back the bind error as is, possibly wrapped, or only as
+/// text once it's gone through its own error type.
+fn is_address_in_use_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|it| it.kind() == std::io::ErrorKind::AddrInUse)
+            || cause.to_string().contains("Address already in use")
+    })
+}
+
+/// The bits of the running service that get reconfigured in place.
+trait ReconfigurableService {
+    fn set_download_path(&mut self, path: Option<PathBuf>);
+}
+
+impl ReconfigurableService for rqs_lib::RQS {
+    fn set_download_path(&mut self, path: Option<PathBuf>) {
+        rqs_lib::RQS::set_download_path(self, path);
+    }
+}
+
+/// Stops and sets the service back up while holding `service_ops_lock`, so
+/// that restarts requested in quick succession run one after another.
+async fn restart_service_serialized(
+    service_ops_lock: &tokio::sync::Mutex<()>,
+    stop: impl AsyncFnOnce(),
+    setup: impl AsyncFnOnce(),
+) {
+    let _service_ops_guard = service_ops_lock.lock().await;
+    stop().await;
+    setup().await;
+}
+
+/// Waits out a restart that might be going on before pointing the service at
+/// `folder_path`, `rqs` would be replaced underneath us otherwise.
+///
+/// Returns whether there was a service to apply it to.
+async fn set_service_download_path<S: ReconfigurableService>(
+    service_ops_lock: &tokio::sync::Mutex<()>,
+    rqs: &tokio::sync::Mutex<Option<S>>,
+    folder_path: PathBuf,
+) -> bool {
+    let _service_ops_guard = service_ops_lock.lock().await;
+    if let Some(rqs) = rqs.lock().await.as_mut() {
+        rqs.set_download_path(Some(folder_path));
+        true
+    } else {
+        false
+    }
+}
+
 mod imp {
     use std::{
         cell::{Cell, RefCell},
-        collections::HashMap,
+        collections::{HashMap, VecDeque},
         rc::Rc,
         sync::Arc,
     };
 
     use tokio::sync::Mutex;
 
-    use crate::{ext::MessageExt, utils::remove_notification};
+    use crate::utils::remove_all_notifications;
 
     use super::*;
 
@@ -73,6 +246,10 @@ mod imp {
         pub rqs_error_copy_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub rqs_error_retry_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub rqs_error_status: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub rqs_error_dynamic_port_button: TemplateChild<gtk::Button>,
 
         #[template_child]
         pub toast_overlay: TemplateChild<adw::ToastOverlay>,
@@ -87,6 +264,30 @@ mod imp {
         #[template_child]
         pub bottom_bar_caption: TemplateChild<gtk::Label>,
         #[template_child]
+        pub bottom_bar_caption_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub bottom_bar_copy_name_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub bottom_bar_visibility_menu_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub bottom_bar_retry_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub bottom_bar_receive_progress: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub bottom_bar_receive_progress_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub bottom_bar_receive_progress_bar: TemplateChild<gtk::ProgressBar>,
+        #[template_child]
+        pub bottom_bar_receive_cancel_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub clipboard_offer_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub clipboard_offer_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub clipboard_offer_send_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub clipboard_offer_dismiss_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub bottom_bar_spacer: TemplateChild<adw::Bin>,
         #[template_child]
         pub bottom_bar_status: TemplateChild<gtk::Box>,
@@ -97,17 +298,82 @@ mod imp {
         pub device_name_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
         pub device_visibility_switch: TemplateChild<adw::SwitchRow>,
+        pub device_visibility_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
         #[template_child]
         pub static_port_expander: TemplateChild<adw::ExpanderRow>,
         #[template_child]
         pub static_port_entry: TemplateChild<adw::EntryRow>,
         #[template_child]
+        pub network_interface_combo_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub discovery_idle_timeout_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub eager_discovery_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub steady_time_estimates_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
         pub download_folder_row: TemplateChild<adw::ActionRow>,
         #[template_child]
         pub download_folder_pick_button: TemplateChild<gtk::Button>,
         #[template_child]
-        pub run_in_background_switch: TemplateChild<adw::SwitchRow>,
-        pub run_in_background_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub text_save_folder_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub text_save_folder_pick_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub text_save_folder_clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub organize_by_date_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub per_device_folders_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub per_device_folder_entry: TemplateChild<adw::EntryRow>,
+        pub per_device_folder_rows: RefCell<Vec<adw::ActionRow>>,
+        #[template_child]
+        pub trusted_devices_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub trusted_device_entry: TemplateChild<adw::EntryRow>,
+        pub trusted_device_rows: RefCell<Vec<adw::ActionRow>>,
+        #[template_child]
+        pub blocked_extensions_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub auto_decline_blocked_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub blocked_extension_entry: TemplateChild<adw::EntryRow>,
+        pub blocked_extension_rows: RefCell<Vec<adw::ActionRow>>,
+        #[template_child]
+        pub receive_only_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub reset_settings_button_row: TemplateChild<adw::ButtonRow>,
+        #[template_child]
+        pub consent_timeout_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub show_timeout_toast_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub receive_ui_style_combo_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub notification_level_combo_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub accent_color_combo_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub allow_empty_files_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub watch_clipboard_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub max_files_warn_threshold_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub max_size_warn_threshold_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub history_retention_days_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub clear_history_button_row: TemplateChild<adw::ButtonRow>,
+        #[template_child]
+        pub short_text_auto_copy_spin_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub verify_received_files_switch: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub auto_open_received_combo_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub close_action_combo_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub auto_start_switch: TemplateChild<adw::SwitchRow>,
         pub auto_start_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
@@ -115,6 +381,17 @@ mod imp {
         pub nautilus_plugin_switch: TemplateChild<adw::SwitchRow>,
         pub nautilus_plugin_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
         #[template_child]
+        pub nautilus_plugin_warning_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub dolphin_plugin_switch: TemplateChild<adw::SwitchRow>,
+        pub dolphin_plugin_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        #[template_child]
+        pub nemo_plugin_switch: TemplateChild<adw::SwitchRow>,
+        pub nemo_plugin_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        #[template_child]
+        pub thunar_plugin_switch: TemplateChild<adw::SwitchRow>,
+        pub thunar_plugin_switch_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        #[template_child]
         pub tray_icon_group: TemplateChild<adw::PreferencesGroup>,
         #[template_child]
         pub tray_icon_switch: TemplateChild<adw::SwitchRow>,
@@ -125,6 +402,10 @@ mod imp {
         pub main_nav_content: TemplateChild<adw::StatusPage>,
         #[template_child]
         pub main_add_files_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub main_resend_files_button: TemplateChild<gtk::Button>,
+        /// Files from the last time the recipients dialog was closed, for resending.
+        pub previous_files: RefCell<Vec<gio::File>>,
 
         #[template_child]
         pub manage_files_nav_content: TemplateChild<gtk::Box>,
@@ -134,40 +415,72 @@ mod imp {
         pub manage_files_add_files_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub manage_files_send_button: TemplateChild<gtk::Button>,
+        pub send_files_drop_targets: RefCell<Vec<gtk::DropTarget>>,
+        pub is_recipient_page_set_up: Cell<bool>,
         #[template_child]
         pub manage_files_listbox: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub recent_recipients_group: TemplateChild<adw::PreferencesGroup>,
+        pub recent_recipient_rows: RefCell<Vec<adw::ActionRow>>,
         #[default(gio::ListStore::new::<gio::File>())]
         pub manage_files_model: gio::ListStore,
 
         #[template_child]
         pub select_recipients_dialog: TemplateChild<adw::Dialog>,
         #[template_child]
+        pub manual_recipient_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
         pub select_recipient_refresh_button: TemplateChild<gtk::Button>,
         #[template_child]
+        pub select_recipient_retry_failed_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub select_recipient_send_all_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub select_recipient_send_all_progress_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub select_recipient_clear_completed_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub recipient_listbox: TemplateChild<gtk::ListBox>,
         #[template_child]
         pub loading_recipients_box: TemplateChild<gtk::Box>,
         #[template_child]
+        pub no_network_status_page: TemplateChild<adw::StatusPage>,
+        #[template_child]
         pub recipients_help_button: TemplateChild<gtk::LinkButton>,
         #[default(gio::ListStore::new::<SendRequestState>())]
         pub recipient_model: gio::ListStore,
+        /// Sorted view over `recipient_model` that the ListBox is bound to,
+        /// any changes should go through the store instead.
+        #[default(gtk::SortListModel::new(None::<gio::ListModel>, None::<gtk::Sorter>))]
+        pub recipient_sorted_model: gtk::SortListModel,
 
         pub send_transfers_id_cache: Arc<Mutex<HashMap<String, SendRequestState>>>, // id, state
+        /// Sends waiting for the active transfer to settle, in the order
+        /// they'll be started in.
+        pub send_queue: RefCell<VecDeque<SendRequestState>>,
+        /// Recipients of the last "Send to All", for showing the overall progress.
+        pub send_all_recipients: RefCell<Vec<SendRequestState>>,
         pub receive_transfer_cache: Arc<Mutex<Option<ReceiveTransferCache>>>,
 
         #[default(gio::NetworkMonitor::default())]
         pub network_monitor: gio::NetworkMonitor,
         pub dbus_system_conn: Rc<RefCell<Option<zbus::Connection>>>,
+        pub bluetooth_state_sender: RefCell<Option<watch::Sender<bool>>>,
         // Would do unwrap_or_default anyways, so keeping it as just bool
-        pub network_state: Rc<Cell<bool>>,
+        pub network_state: Rc<Cell<monitors::NetworkState>>,
         pub bluetooth_state: Rc<Cell<bool>>,
 
-        // FIXME: use this to receive network state on send/receive transfers, to cancel them
-        // on connection loss
-        pub network_state_sender: Arc<Mutex<Option<tokio::sync::broadcast::Sender<bool>>>>,
+        /// Sent on every change of `network_state` or `bluetooth_state`, for
+        /// anything that has to react to the connection coming and going.
+        #[default(tokio::sync::broadcast::channel(8).0)]
+        pub connectivity_sender: tokio::sync::broadcast::Sender<monitors::ConnectivityState>,
 
         // RQS State
         pub rqs: Arc<Mutex<Option<rqs_lib::RQS>>>,
+        /// Held through anything that restarts or reconfigures the service, so
+        /// that folder/port/name changes can't interleave and end up acting on
+        /// an `rqs` that's being replaced
+        pub service_ops_lock: Rc<Mutex<()>>,
         pub file_sender: Arc<Mutex<Option<tokio::sync::mpsc::Sender<rqs_lib::SendInfo>>>>,
         pub ble_receiver: Arc<Mutex<Option<tokio::sync::broadcast::Receiver<()>>>>,
         pub mdns_discovery_broadcast_tx:
@@ -177,14 +490,32 @@ mod imp {
         pub looping_async_tasks: RefCell<Vec<LoopingTaskHandle>>,
 
         pub is_background_allowed: Cell<bool>,
+        /// Trusted devices accepted by hand this session. Devices are trusted
+        /// going by the name they send, which anyone can claim, so they're
+        /// asked about once before being accepted right away.
+        pub confirmed_trusted_devices: RefCell<std::collections::HashSet<String>>,
         pub should_quit: Cell<bool>,
 
+        pub temporary_visibility_token: RefCell<Option<CancellationToken>>,
+
+        pub clipboard_offer_files: RefCell<Vec<gio::File>>,
+        pub clipboard_offer_ctk: RefCell<Option<CancellationToken>>,
+        pub temporary_visibility_deadline: Cell<Option<std::time::Instant>>,
+
+        pub idle_pause_token: RefCell<Option<CancellationToken>>,
+        pub is_idle_paused: Cell<bool>,
+        pub was_mdns_discovery_on_before_idle: Cell<bool>,
+
         pub is_recipients_dialog_opened: Cell<bool>,
+        /// Composed Wi-Fi networks, kept on disk only until they're sent
+        pub wifi_credentials_files: RefCell<Vec<widgets::WifiCredentialsFile>>,
 
         pub nautilus_plugin: NautilusPlugin,
+        pub dolphin_plugin: DolphinPlugin,
+        pub nemo_plugin: NemoPlugin,
+        pub thunar_plugin: ThunarPlugin,
 
-        #[cfg(target_os = "linux")]
-        pub tray_icon_handle: RefCell<Option<ksni::Handle<crate::tray::Tray>>>,
+        pub tray_icon_handle: RefCell<Option<Box<dyn crate::tray::TrayBackend>>>,
     }
 
     #[glib::object_subclass]
@@ -218,7 +549,6 @@ mod imp {
             obj.load_app_state();
             obj.setup_gactions();
             obj.setup_preferences();
-            #[cfg(target_os = "linux")]
             obj.setup_tray_icon();
             obj.setup_ui();
             obj.setup_connection_monitors();
@@ -232,13 +562,18 @@ mod imp {
     impl WindowImpl for PacketApplicationWindow {
         // Save window state on delete event
         fn close_request(&self) -> glib::Propagation {
-            if self.is_background_allowed.get()
-                && self.settings.boolean("run-in-background")
-                && !self.should_quit.get()
-            {
-                tracing::info!("Running Packet in background");
-                self.obj().set_visible(false);
-                return glib::Propagation::Stop;
+            if !self.should_quit.get() {
+                match self.settings.string("close-action").as_str() {
+                    "hide" => {
+                        self.obj().hide_to_background();
+                        return glib::Propagation::Stop;
+                    }
+                    "ask" => {
+                        self.obj().present_close_action_dialog();
+                        return glib::Propagation::Stop;
+                    }
+                    _ => {}
+                }
             }
 
             tracing::debug!("GtkApplicationWindow<PacketApplicationWindow>::close");
@@ -250,27 +585,9 @@ mod imp {
                 tracing::warn!("Failed to save app state, {}", &err);
             }
 
-            if let Some(cached_transfer) = self.receive_transfer_cache.blocking_lock().as_ref() {
-                use rqs_lib::TransferState;
-                match cached_transfer
-                    .state
-                    .event()
-                    .unwrap()
-                    .msg
-                    .as_client_unchecked()
-                    .state
-                    .as_ref()
-                    .unwrap_or(&TransferState::Initial)
-                {
-                    TransferState::Disconnected
-                    | TransferState::Rejected
-                    | TransferState::Cancelled
-                    | TransferState::Finished => {}
-                    _ => {
-                        remove_notification(cached_transfer.notification_id.clone());
-                    }
-                }
-            }
+            // Notifications would otherwise outlast the app, with their
+            // actions leading nowhere
+            remove_all_notifications();
 
             // Abort all looping tasks before closing
             tracing::info!(
@@ -324,13 +641,38 @@ impl PacketApplicationWindow {
         glib::Object::builder().property("application", app).build()
     }
 
+    /// Closes the window for good, so that the app quits.
+    ///
+    /// With a dialog open, closing the window only closes the dialog, so any
+    /// open dialogs are closed first.
+    pub fn quit_window(&self) {
+        self.imp().should_quit.replace(true);
+
+        if let Some(dialog) = self.visible_dialog() {
+            // Dialogs are only gone once their closing animation is done,
+            // then the next one in line or the window itself can go
+            dialog.connect_closed(clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_| this.quit_window()
+            ));
+            dialog.force_close();
+            return;
+        }
+
+        self.close();
+    }
+
     fn save_window_size(&self) -> Result<(), glib::BoolError> {
         let imp = self.imp();
 
-        let (width, height) = self.default_size();
+        // Keep the size of the full layout around for when mini mode is toggled off
+        if !imp.settings.boolean("mini-mode") {
+            let (width, height) = self.default_size();
 
-        imp.settings.set_int("window-width", width)?;
-        imp.settings.set_int("window-height", height)?;
+            imp.settings.set_int("window-width", width)?;
+            imp.settings.set_int("window-height", height)?;
+        }
 
         imp.settings
             .set_boolean("is-maximized", self.is_maximized())?;
@@ -358,6 +700,19 @@ impl PacketApplicationWindow {
         imp.settings
             .set_string("device-name", imp.device_name_entry.text().as_str())?;
 
+        // Temporary visibility shouldn't outlive the session
+        if imp.temporary_visibility_deadline.get().is_some() {
+            imp.settings.set_boolean("device-visibility", false)?;
+        }
+
+        let is_always_on_top = self
+            .lookup_action("toggle-always-on-top")
+            .and_then(|it| it.state())
+            .and_then(|it| it.get::<bool>())
+            .unwrap_or_default();
+        imp.settings
+            .set_boolean("always-on-top", is_always_on_top)?;
+
         Ok(())
     }
 
@@ -372,6 +727,10 @@ impl PacketApplicationWindow {
                 .unwrap();
         }
 
+        self.validate_and_repair_settings();
+        self.migrate_run_in_background();
+        self.prune_recipient_history();
+
         imp.settings
             .bind(
                 "enable-static-port",
@@ -381,6 +740,156 @@ impl PacketApplicationWindow {
             .build();
         imp.static_port_entry
             .set_text(&imp.settings.int("static-port-number").to_string());
+
+        if imp.settings.boolean("mini-mode") {
+            self.apply_mini_mode(true);
+        }
+        if imp.settings.boolean("always-on-top") {
+            self.apply_always_on_top(true);
+        }
+    }
+
+    fn apply_always_on_top(&self, is_always_on_top: bool) {
+        let imp = self.imp();
+
+        tracing::info!(is_always_on_top, "Setting always on top");
+        if !is_always_on_top {
+            return;
+        }
+
+        // GTK 4 dropped `gtk_window_set_keep_above`, and neither Wayland nor the
+        // portals let apps ask for it, so the preference is only kept around for
+        // when it can be honored. Said once, rather than on every start.
+        tracing::warn!("Keeping the window above others isn't supported");
+        if !imp.settings.boolean("always-on-top-notice-shown") {
+            imp.toast_overlay.add_toast(
+                adw::Toast::builder()
+                    .title(&gettext(
+                        "Can't keep the window on top here, use your desktop's window menu instead",
+                    ))
+                    .timeout(0)
+                    .build(),
+            );
+            _ = imp
+                .settings
+                .set_boolean("always-on-top-notice-shown", true)
+                .inspect_err(|err| tracing::warn!("{err:#}"));
+        }
+    }
+
+    /// Switches the main page to a compact layout that's just the drop target
+    /// and the bottom bar, or back to the full one.
+    fn apply_mini_mode(&self, is_mini_mode: bool) {
+        let imp = self.imp();
+
+        tracing::info!(is_mini_mode, "Setting mini mode");
+
+        if is_mini_mode {
+            imp.main_nav_content.add_css_class("compact");
+            imp.main_nav_content.set_icon_name(None);
+
+            self.update_main_page_description();
+
+            self.unmaximize();
+            self.set_default_size(MINI_MODE_WIDTH, MINI_MODE_HEIGHT);
+        } else {
+            imp.main_nav_content.remove_css_class("compact");
+            imp.main_nav_content
+                .set_icon_name(Some("io.github.nozwock.Packet"));
+            self.update_main_page_description();
+
+            self.set_default_size(
+                imp.settings.int("window-width"),
+                imp.settings.int("window-height"),
+            );
+            if imp.settings.boolean("is-maximized") {
+                self.maximize();
+            }
+        }
+    }
+
+    /// The preference values are only validated in the UI, so this takes care
+    /// of the values that were modified outside of the app to something invalid
+    /// (e.g. via `gsettings` or `dconf-editor`), by resetting them to their
+    /// defaults.
+    fn validate_and_repair_settings(&self) {
+        let imp = self.imp();
+
+        let mut is_any_repaired = false;
+
+        let static_port_number = imp.settings.int("static-port-number");
+        if !is_valid_static_port(static_port_number) {
+            tracing::warn!(
+                static_port_number,
+                "Invalid static port number in settings. Resetting to default"
+            );
+
+            imp.settings.reset("static-port-number");
+            is_any_repaired = true;
+        }
+
+        // An empty device name is fine, it gets replaced with the system's device
+        // name in `setup_preferences`
+        let device_name = imp.settings.string("device-name");
+        if !device_name.is_empty() && device_name.trim().is_empty() {
+            tracing::warn!(
+                ?device_name,
+                "Invalid device name in settings. Resetting to default"
+            );
+
+            imp.settings.reset("device-name");
+            is_any_repaired = true;
+        }
+
+        // Check if we still have access to the set "Downloads Folder"
+        let download_folder = imp.settings.string("download-folder");
+        let download_folder_exists = std::fs::exists(&download_folder).unwrap_or_default();
+        if !download_folder_exists {
+            let fallback = xdg_download_with_fallback();
+
+            tracing::warn!(
+                ?download_folder,
+                ?fallback,
+                "Couldn't access Downloads folder. Resetting to fallback"
+            );
+
+            // Fallback for when user doesn't select a download folder when prompted
+            imp.settings
+                .set_string("download-folder", fallback.to_str().unwrap())
+                .unwrap();
+
+            imp.toast_overlay.add_toast(
+                adw::Toast::builder()
+                    .title(&gettext("Can't access Downloads folder"))
+                    .button_label(&gettext("Pick Folder"))
+                    .action_name("win.pick-download-folder")
+                    .build(),
+            );
+        }
+
+        if is_any_repaired {
+            self.add_toast(&gettext("Some invalid preferences were reset"));
+        }
+    }
+
+    /// `run-in-background` used to decide whether closing the window quit the
+    /// app, so it's carried over into `close-action` unless that's been set
+    /// since. It's reset afterwards, so that this only ever happens once.
+    fn migrate_run_in_background(&self) {
+        let imp = self.imp();
+
+        let Some(run_in_background) = imp.settings.user_value("run-in-background") else {
+            return;
+        };
+        if imp.settings.user_value("close-action").is_none() {
+            let close_action = match run_in_background.get::<bool>() {
+                Some(true) => "hide",
+                _ => "quit",
+            };
+            tracing::info!(close_action, "Migrating run-in-background to close-action");
+            _ = imp.settings.set_string("close-action", close_action);
+        }
+        imp.settings.reset("run-in-background");
     }
 
     fn setup_gactions(&self) {
@@ -406,6 +915,15 @@ impl PacketApplicationWindow {
             })
             .build();
 
+        let copy_path = gio::ActionEntry::builder("copy-path")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |win: &Self, _, param| {
+                if let Some(path) = param.and_then(|it| it.get::<String>()) {
+                    win.clipboard().set_text(&path);
+                }
+            })
+            .build();
+
         let help_dialog = gio::ActionEntry::builder("help")
             .activate(move |win: &Self, _, _| {
                 win.imp()
@@ -414,18 +932,217 @@ impl PacketApplicationWindow {
             })
             .build();
 
+        let diagnostics = gio::ActionEntry::builder("diagnostics")
+            .activate(move |win: &Self, _, _| {
+                widgets::present_diagnostics_dialog(win);
+            })
+            .build();
+
         let pick_download_folder = gio::ActionEntry::builder("pick-download-folder")
             .activate(move |win: &Self, _, _| {
                 win.pick_download_folder();
             })
             .build();
 
+        let toggle_mini_mode = gio::ActionEntry::builder("toggle-mini-mode")
+            .state(self.imp().settings.boolean("mini-mode").to_variant())
+            .activate(move |win: &Self, action, _| {
+                let is_mini_mode = !action
+                    .state()
+                    .and_then(|it| it.get::<bool>())
+                    .unwrap_or_default();
+                action.set_state(&is_mini_mode.to_variant());
+
+                // Remember the size of the full layout to restore it later
+                if is_mini_mode && let Err(err) = win.save_window_size() {
+                    tracing::warn!("Failed to save window state, {}", &err);
+                }
+                _ = win
+                    .imp()
+                    .settings
+                    .set_boolean("mini-mode", is_mini_mode)
+                    .inspect_err(|err| tracing::warn!("{err:#}"));
+                win.apply_mini_mode(is_mini_mode);
+            })
+            .build();
+
+        let toggle_always_on_top = gio::ActionEntry::builder("toggle-always-on-top")
+            .state(self.imp().settings.boolean("always-on-top").to_variant())
+            .activate(move |win: &Self, action, _| {
+                let is_always_on_top = !action
+                    .state()
+                    .and_then(|it| it.get::<bool>())
+                    .unwrap_or_default();
+                action.set_state(&is_always_on_top.to_variant());
+
+                win.apply_always_on_top(is_always_on_top);
+            })
+            .build();
+
+        let visibility_mode = gio::ActionEntry::builder("visibility-mode")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .state(self.visibility_mode().to_variant())
+            .activate(move |win: &Self, _, param| {
+                if let Some(mode) = param.and_then(|it| it.get::<String>()) {
+                    win.set_visibility_mode(&mode);
+                }
+            })
+            .build();
+
+        let receive_temporarily = gio::ActionEntry::builder("receive-temporarily")
+            .activate(move |win: &Self, _, _| {
+                win.start_temporary_visibility();
+            })
+            .build();
+
+        let send_clipboard = gio::ActionEntry::builder("send-clipboard")
+            .activate(move |win: &Self, _, _| {
+                win.send_clipboard();
+            })
+            .build();
+
+        let compose_wifi = gio::ActionEntry::builder("compose-wifi")
+            .activate(move |win: &Self, _, _| {
+                if win.imp().settings.boolean("receive-only-mode") {
+                    win.add_toast(&gettext("Sending is turned off in receive only mode"));
+                    return;
+                }
+
+                widgets::present_wifi_composer_dialog(win);
+            })
+            .build();
+
         self.add_action_entries([
             preferences_dialog,
             received_files,
+            copy_path,
             help_dialog,
+            diagnostics,
             pick_download_folder,
+            toggle_mini_mode,
+            toggle_always_on_top,
+            visibility_mode,
+            receive_temporarily,
+            send_clipboard,
+            compose_wifi,
         ]);
+
+        if PROFILE == "Devel" {
+            let simulate_receive = gio::ActionEntry::builder("simulate-receive")
+                .activate(move |win: &Self, _, _| {
+                    win.simulate_receive_transfer();
+                })
+                .build();
+            self.add_action_entries([simulate_receive]);
+        }
+    }
+
+    /// Plays out a made up incoming transfer through the same path the real
+    /// ones take, for working on the receive UI without a second device.
+    fn simulate_receive_transfer(&self) {
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                if let Err(err) = this.run_simulated_receive_transfer().await {
+                    tracing::warn!("{:#}", err.context("Simulated transfer failed"));
+                }
+            }
+        ));
+    }
+
+    async fn run_simulated_receive_transfer(&self) -> anyhow::Result<()> {
+        use rqs_lib::channel::{
+            ChannelMessage, Message, MessageClient, TransferAction, TransferKind,
+        };
+        use rqs_lib::hdl::info::{
+            RemoteDeviceInfo, TransferMetadata, TransferPayload, TransferPayloadKind,
+        };
+
+        const TOTAL_BYTES: u64 = 64 * 1024 * 1024;
+        const PROGRESS_STEPS: u64 = 20;
+        const PROGRESS_STEP_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+        let message_sender = self
+            .imp()
+            .rqs
+            .lock()
+            .await
+            .as_ref()
+            .context("The service isn't running")?
+            .message_sender
+            .clone();
+        // The user's responses go out on the same channel
+        let mut lib_rx = message_sender.subscribe();
+
+        let id = format!("simulated:{}", glib::uuid_string_random());
+        tracing::info!(%id, "Simulating an incoming transfer");
+
+        let send_state = |state: rqs_lib::TransferState, ack_bytes: u64| {
+            message_sender
+                .send(ChannelMessage {
+                    id: id.clone(),
+                    msg: Message::Client(MessageClient {
+                        kind: TransferKind::Inbound,
+                        state: Some(state),
+                        metadata: Some(TransferMetadata {
+                            source: Some(RemoteDeviceInfo {
+                                name: "Simulated Device".into(),
+                                ..Default::default()
+                            }),
+                            pin_code: Some("1234".into()),
+                            payload_kind: TransferPayloadKind::Files,
+                            payload: Some(TransferPayload::Files(vec![
+                                "Screenshot.png".into(),
+                                "Notes.txt".into(),
+                            ])),
+                            total_bytes: TOTAL_BYTES,
+                            ack_bytes,
+                            ..Default::default()
+                        }),
+                    }),
+                })
+                .map(|_| ())
+                .map_err(|err| anyhow!("Couldn't send a simulated event: {err}"))
+        };
+
+        let mut next_user_action = async || -> anyhow::Result<TransferAction> {
+            loop {
+                let msg = lib_rx.recv().await?;
+                if msg.id != id {
+                    continue;
+                }
+                if let Message::Lib { action } = msg.msg {
+                    return Ok(action);
+                }
+            }
+        };
+
+        send_state(rqs_lib::TransferState::WaitingForUserConsent, 0)?;
+        match next_user_action().await? {
+            TransferAction::ConsentAccept => {}
+            TransferAction::ConsentDecline => {
+                return send_state(rqs_lib::TransferState::Rejected, 0);
+            }
+            _ => return send_state(rqs_lib::TransferState::Cancelled, 0),
+        }
+
+        for step in 1..=PROGRESS_STEPS {
+            tokio::select! {
+                action = next_user_action() => {
+                    tracing::info!(?action, "Simulated transfer interrupted");
+                    return send_state(rqs_lib::TransferState::Cancelled, 0);
+                }
+                _ = futures_timer::Delay::new(PROGRESS_STEP_DELAY) => {}
+            };
+
+            send_state(
+                rqs_lib::TransferState::ReceivingFiles,
+                TOTAL_BYTES * step / PROGRESS_STEPS,
+            )?;
+        }
+
+        send_state(rqs_lib::TransferState::Finished, TOTAL_BYTES)
     }
 
     fn add_toast(&self, msg: &str) {
@@ -454,38 +1171,257 @@ impl PacketApplicationWindow {
             .build();
         imp.settings
             .bind(
-                "run-in-background",
-                &imp.run_in_background_switch.get(),
+                "receive-only-mode",
+                &imp.receive_only_switch.get(),
                 "active",
             )
             .build();
         imp.settings
-            .bind("auto-start", &imp.auto_start_switch.get(), "active")
+            .bind(
+                "auto-decline-blocked-extensions",
+                &imp.auto_decline_blocked_switch.get(),
+                "active",
+            )
             .build();
+        imp.settings.connect_changed(
+            Some("trusted-only"),
+            clone!(
+                #[weak]
+                imp,
+                move |_, _| {
+                    imp.obj().bottom_bar_status_indicator_ui_update(
+                        imp.device_visibility_switch.is_active(),
+                    );
+                }
+            ),
+        );
         imp.settings
             .bind(
-                "enable-nautilus-plugin",
-                &imp.nautilus_plugin_switch.get(),
+                "discovery-idle-timeout",
+                &imp.discovery_idle_timeout_spin_row.get(),
+                "value",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "eager-discovery",
+                &imp.eager_discovery_switch.get(),
                 "active",
             )
             .build();
+        imp.settings.connect_changed(
+            Some("eager-discovery"),
+            clone!(
+                #[weak]
+                imp,
+                move |_, _| {
+                    imp.obj().update_eager_discovery();
+                }
+            ),
+        );
         imp.settings
-            .bind("enable-tray-icon", &imp.tray_icon_switch.get(), "active")
+            .bind(
+                "consent-timeout-seconds",
+                &imp.consent_timeout_spin_row.get(),
+                "value",
+            )
             .build();
-
-        // TODO: The value of many preference options are only validated in the
-        // UI, not outside of it.
-        //
-        // Incase the users modifies the setting value outside of the app to
-        // something invalid, might want to take such a scenario into
-        // consideration.
-        //
-        // For example the port value with non-numeric value, display name with
-        // non-UTF8 bytes, etc.
-
-        let device_name = &self.get_device_name_state();
-        let device_name_entry = imp.device_name_entry.get();
-        {
+        imp.settings
+            .bind(
+                "show-timeout-toast",
+                &imp.show_timeout_toast_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "receive-ui-style",
+                &imp.receive_ui_style_combo_row.get(),
+                "selected",
+            )
+            .mapping(|variant, _| {
+                let style = variant.str()?;
+                let idx = RECEIVE_UI_STYLES.iter().position(|it| *it == style)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                RECEIVE_UI_STYLES
+                    .get(idx as usize)
+                    .map(|it| it.to_variant())
+            })
+            .build();
+        imp.settings
+            .bind(
+                "accent-color",
+                &imp.accent_color_combo_row.get(),
+                "selected",
+            )
+            .mapping(|variant, _| {
+                let accent_color = variant.str()?;
+                let idx = ACCENT_COLORS.iter().position(|it| *it == accent_color)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                ACCENT_COLORS.get(idx as usize).map(|it| it.to_variant())
+            })
+            .build();
+        imp.settings
+            .bind(
+                "notification-level",
+                &imp.notification_level_combo_row.get(),
+                "selected",
+            )
+            .mapping(|variant, _| {
+                let level = variant.str()?;
+                let idx = NOTIFICATION_LEVELS.iter().position(|it| *it == level)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                NOTIFICATION_LEVELS
+                    .get(idx as usize)
+                    .map(|it| it.to_variant())
+            })
+            .build();
+        imp.settings
+            .bind(
+                "allow-empty-files",
+                &imp.allow_empty_files_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "watch-clipboard",
+                &imp.watch_clipboard_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "max-files-warn-threshold",
+                &imp.max_files_warn_threshold_spin_row.get(),
+                "value",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "max-size-warn-threshold",
+                &imp.max_size_warn_threshold_spin_row.get(),
+                "value",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "history-retention-days",
+                &imp.history_retention_days_spin_row.get(),
+                "value",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "short-text-auto-copy",
+                &imp.short_text_auto_copy_spin_row.get(),
+                "value",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "organize-by-date",
+                &imp.organize_by_date_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "verify-received-files",
+                &imp.verify_received_files_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "steady-time-estimates",
+                &imp.steady_time_estimates_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "auto-open-received",
+                &imp.auto_open_received_combo_row.get(),
+                "selected",
+            )
+            .mapping(|variant, _| {
+                let option = variant.str()?;
+                let idx = AUTO_OPEN_RECEIVED_OPTIONS
+                    .iter()
+                    .position(|it| *it == option)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                AUTO_OPEN_RECEIVED_OPTIONS
+                    .get(idx as usize)
+                    .map(|it| it.to_variant())
+            })
+            .build();
+        imp.settings
+            .bind(
+                "close-action",
+                &imp.close_action_combo_row.get(),
+                "selected",
+            )
+            .mapping(|variant, _| {
+                let action = variant.str()?;
+                let idx = CLOSE_ACTIONS.iter().position(|it| *it == action)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                CLOSE_ACTIONS.get(idx as usize).map(|it| it.to_variant())
+            })
+            .build();
+        imp.settings
+            .bind("auto-start", &imp.auto_start_switch.get(), "active")
+            .build();
+        imp.settings
+            .bind(
+                "enable-nautilus-plugin",
+                &imp.nautilus_plugin_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "enable-dolphin-plugin",
+                &imp.dolphin_plugin_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "enable-nemo-plugin",
+                &imp.nemo_plugin_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind(
+                "enable-thunar-plugin",
+                &imp.thunar_plugin_switch.get(),
+                "active",
+            )
+            .build();
+        imp.settings
+            .bind("enable-tray-icon", &imp.tray_icon_switch.get(), "active")
+            .build();
+
+        let device_name = &self.get_device_name_state();
+        let device_name_entry = imp.device_name_entry.get();
+        {
             if device_name.is_empty() {
                 let device_name = whoami::devicename();
                 device_name_entry.set_text(&device_name);
@@ -498,90 +1434,47 @@ impl PacketApplicationWindow {
             }
         }
 
-        if imp.settings.boolean("enable-nautilus-plugin") {
-            // Update plugin
-            // This takes care of cases of applying updates to the python extension
-            // script as well as reinstalling it if it got removed for some reason.
-            let plugin = imp.nautilus_plugin.clone();
-            glib::spawn_future_local(clone!(
-                #[weak]
-                imp,
-                async move {
-                    let success = tokio_runtime()
-                        .spawn_blocking(move || plugin.install_plugin())
-                        .await
-                        .map_err(|err| anyhow::anyhow!(err))
-                        .and_then(|it| it)
-                        .inspect_err(|err| tracing::error!("{err:#}"))
-                        .is_ok();
-
-                    if !success {
-                        imp.obj()
-                            .add_toast(&gettext("Couldn't update the Nautilus plugin"));
-                    }
-                }
-            ));
-        }
-
-        let _signal_handle = imp.nautilus_plugin_switch.connect_active_notify(clone!(
-            #[weak]
-            imp,
-            move |switch| {
-                glib::spawn_future_local(clone!(
-                    #[weak]
-                    imp,
-                    #[weak]
-                    switch,
-                    async move {
-                        switch.set_sensitive(false);
-
-                        let enable_plugin = switch.is_active();
-
-                        tracing::info!(enable_plugin, "Setting Nautilus plugin state");
-
-                        let plugin = imp.nautilus_plugin.clone();
-                        let success = tokio_runtime()
-                            .spawn_blocking(move || {
-                                if enable_plugin {
-                                    plugin.install_plugin()
-                                } else {
-                                    plugin.uninstall_plugin()
-                                }
-                            })
-                            .await
-                            .map_err(|err| anyhow::anyhow!(err))
-                            .and_then(|it| it)
-                            .inspect_err(|err| tracing::error!("{err:#}"))
-                            .is_ok();
-
-                        if enable_plugin {
-                            if success {
-                                imp.obj().present_plugin_success_dialog();
-                            } else {
-                                imp.obj().present_plugin_error_dialog(
-                                        NautilusPlugin::help_install_dir(),
-                                    );
-                                with_signals_blocked(
-                                    &[(
-                                        &switch,
-                                        imp.nautilus_plugin_switch_handler_id.borrow().as_ref(),
-                                    )],
-                                    || {
-                                        switch.set_active(false);
-                                    },
-                                );
-                            }
-                        }
-
-                        switch.set_sensitive(true);
-                    }
-                ));
+        self.setup_plugin_switch(
+            imp.nautilus_plugin.clone(),
+            "Nautilus",
+            &imp.nautilus_plugin_switch.get(),
+            |imp| &imp.nautilus_plugin_switch_handler_id,
+            NautilusPlugin::help_install_dir(),
+            Self::present_plugin_success_dialog,
+        );
+        self.check_nautilus_plugin_deps();
+        imp.nautilus_plugin_switch.connect_active_notify(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.check_nautilus_plugin_deps();
             }
         ));
-        imp.nautilus_plugin_switch_handler_id
-            .replace(Some(_signal_handle));
+        self.setup_plugin_switch(
+            imp.dolphin_plugin.clone(),
+            "Dolphin",
+            &imp.dolphin_plugin_switch.get(),
+            |imp| &imp.dolphin_plugin_switch_handler_id,
+            DolphinPlugin::help_install_dir(),
+            |win| win.present_plugin_restart_dialog("Dolphin"),
+        );
+        self.setup_plugin_switch(
+            imp.nemo_plugin.clone(),
+            "Nemo",
+            &imp.nemo_plugin_switch.get(),
+            |imp| &imp.nemo_plugin_switch_handler_id,
+            NemoPlugin::help_install_dir(),
+            |win| win.present_plugin_restart_dialog("Nemo"),
+        );
+        self.setup_plugin_switch(
+            imp.thunar_plugin.clone(),
+            "Thunar",
+            &imp.thunar_plugin_switch.get(),
+            |imp| &imp.thunar_plugin_switch_handler_id,
+            ThunarPlugin::help_install_dir(),
+            |win| win.present_plugin_restart_dialog("Thunar"),
+        );
 
-        #[cfg(target_os = "linux")]
         imp.tray_icon_switch.connect_active_notify(clone!(
             #[weak]
             imp,
@@ -606,24 +1499,21 @@ impl PacketApplicationWindow {
             }
         ));
 
-        let _signal_handle = imp.run_in_background_switch.connect_active_notify(clone!(
-            #[weak]
-            imp,
-            move |switch| {
-                glib::spawn_future_local(clone!(
-                    #[weak]
-                    imp,
-                    #[weak]
-                    switch,
-                    async move {
-                        switch.set_sensitive(false);
+        imp.settings.connect_changed(
+            Some("close-action"),
+            clone!(
+                #[weak]
+                imp,
+                move |settings, key| {
+                    if settings.string(key) != "hide" {
+                        return;
+                    }
 
-                        {
-                            let is_run_in_background = switch.is_active();
-                            tracing::info!(
-                                is_active = is_run_in_background,
-                                "Setting run in background"
-                            );
+                    glib::spawn_future_local(clone!(
+                        #[weak]
+                        imp,
+                        async move {
+                            tracing::info!("Setting run in background");
 
                             let is_run_in_background_allowed = imp
                                 .obj()
@@ -631,20 +1521,15 @@ impl PacketApplicationWindow {
                                 .await
                                 .map(|it| it.run_in_background())
                                 .unwrap_or_default();
-
-                            if is_run_in_background && !is_run_in_background_allowed {
+                            if !is_run_in_background_allowed {
                                 imp.obj()
                                     .add_toast(&gettext("Packet cannot run in the background"));
                             }
                         }
-
-                        switch.set_sensitive(true);
-                    }
-                ));
-            }
-        ));
-        imp.run_in_background_switch_handler_id
-            .replace(Some(_signal_handle));
+                    ));
+                }
+            ),
+        );
 
         let _signal_handle = imp.auto_start_switch.connect_active_notify(clone!(
             #[weak]
@@ -658,6 +1543,25 @@ impl PacketApplicationWindow {
                     async move {
                         switch.set_sensitive(false);
 
+                        if switch.is_active() && !imp.settings.boolean("auto-start-confirmed") {
+                            if !imp.obj().confirm_auto_start(&switch).await {
+                                with_signals_blocked(
+                                    &[(
+                                        &imp.auto_start_switch.get(),
+                                        imp.auto_start_switch_handler_id.borrow().as_ref(),
+                                    )],
+                                    || {
+                                        _ = imp.settings.set_boolean("auto-start", false);
+                                    },
+                                );
+
+                                switch.set_sensitive(true);
+                                return;
+                            }
+
+                            _ = imp.settings.set_boolean("auto-start-confirmed", true);
+                        }
+
                         {
                             let is_auto_start = switch.is_active();
                             tracing::info!(is_active = is_auto_start, "Setting auto-start");
@@ -815,10 +1719,14 @@ impl PacketApplicationWindow {
                         obj,
                         async move {
                             let port_number = imp.settings.int("static-port-number");
-                            if obj.enables_expansion()
-                                && Some(port_number as u32)
-                                    != imp.rqs.lock().await.as_ref().unwrap().port_number
-                            {
+                            let is_port_changed = {
+                                // Compare against the port of the service that'll be
+                                // running once any ongoing restart is done
+                                let _service_ops_guard = imp.service_ops_lock.lock().await;
+                                imp.rqs.lock().await.as_ref().map(|it| it.port_number)
+                                    != Some(Some(port_number as u32))
+                            };
+                            if obj.enables_expansion() && is_port_changed {
                                 tracing::info!(port_number, "Setting custom static port");
 
                                 // FIXME: maybe just make the widget insensitive
@@ -832,6 +1740,60 @@ impl PacketApplicationWindow {
                 }
             ));
 
+        {
+            // The first option stands for not restricting discovery to an interface
+            let interface_names = utils::network_interface_names();
+            let model = gtk::StringList::new(&[&gettext("Automatic")]);
+            for name in &interface_names {
+                model.append(name);
+            }
+
+            let saved_interface = imp.settings.string("interface-name");
+            let selected = if saved_interface.is_empty() {
+                0
+            } else {
+                match interface_names
+                    .iter()
+                    .position(|it| it == saved_interface.as_str())
+                {
+                    Some(idx) => idx + 1,
+                    None => {
+                        // Keep the choice around even if the interface is down at the moment
+                        model.append(&saved_interface);
+                        interface_names.len() + 1
+                    }
+                }
+            };
+
+            imp.network_interface_combo_row.set_model(Some(&model));
+            imp.network_interface_combo_row
+                .set_selected(selected as u32);
+            imp.network_interface_combo_row
+                .connect_selected_notify(clone!(
+                    #[weak]
+                    imp,
+                    #[weak]
+                    model,
+                    move |obj| {
+                        let interface_name = if obj.selected() == 0 {
+                            glib::GString::default()
+                        } else {
+                            model.string(obj.selected()).unwrap_or_default()
+                        };
+                        imp.settings
+                            .set_string("interface-name", &interface_name)
+                            .unwrap();
+
+                        // rqs_lib binds to all interfaces and doesn't allow choosing
+                        // one, so there's nothing to restart the service for
+                        tracing::warn!(
+                            ?interface_name,
+                            "Network interface selection isn't supported by rqs_lib yet, ignoring"
+                        );
+                    }
+                ));
+        }
+
         let prev_validation_state = Rc::new(Cell::new(None));
         let changed_signal_handle = Rc::new(RefCell::new(None));
         imp.static_port_entry.connect_apply(clone!(
@@ -845,14 +1807,24 @@ impl PacketApplicationWindow {
                 obj.remove_css_class("success");
                 prev_validation_state.set(None);
 
+                let port_text = obj.text();
                 let port_number = {
-                    let port_number = obj.text().as_str().parse::<u16>();
+                    // The entry's validation should've already taken care of this,
+                    // but don't rely on it
+                    let port_number = port_text
+                        .as_str()
+                        .trim()
+                        .parse::<u16>()
+                        .ok()
+                        .filter(|it| is_valid_static_port((*it).into()));
                     tracing::info!(?port_number, "Setting custom static port");
 
-                    port_number.unwrap()
+                    port_number
                 };
 
-                if port_scanner::local_port_available(port_number) {
+                if let Some(port_number) = port_number
+                    && port_scanner::local_port_available(port_number)
+                {
                     imp.settings
                         .set_int("static-port-number", port_number.into())
                         .unwrap();
@@ -861,11 +1833,16 @@ impl PacketApplicationWindow {
 
                     imp.obj().restart_rqs_service();
                 }
-                else if Some(port_number as u32) == imp.rqs.blocking_lock().as_ref().unwrap().port_number {
+                else if port_number.is_some_and(|port_number| {
+                    imp.rqs
+                        .blocking_lock()
+                        .as_ref()
+                        .is_some_and(|rqs| rqs.port_number == Some(port_number as u32))
+                }) {
                     // Don't do anything if port is already set
                 }
                 else {
-                    tracing::info!(port_number, "Port number isn't available");
+                    tracing::info!(port_number = port_text.as_str(), "Port number isn't available");
 
                     // To prevent the apply button from showing after setting the text
                     obj.block_signal(&changed_signal_handle.borrow().as_ref().unwrap());
@@ -882,7 +1859,7 @@ impl PacketApplicationWindow {
                                 gettext(
                                     "The chosen static port \"{}\" is not available. Try a different port above 1024."
                                 ),
-                                port_number
+                                port_text.as_str()
                             )
                             .unwrap_or_default(),
                         )
@@ -907,7 +1884,7 @@ impl PacketApplicationWindow {
                 let parsed_port_number = obj.text().as_str().parse::<u16>();
                 set_entry_validation_state(
                     &obj,
-                    parsed_port_number.is_ok() && parsed_port_number.unwrap() > 1024,
+                    parsed_port_number.is_ok_and(|it| is_valid_static_port(it.into())),
                     &prev_validation_state,
                     changed_signal_handle.borrow().as_ref().unwrap(),
                 );
@@ -915,35 +1892,6 @@ impl PacketApplicationWindow {
         ));
         *changed_signal_handle.as_ref().borrow_mut() = Some(_changed_signal_handle);
 
-        // Check if we still have access to the set "Downloads Folder"
-        {
-            let download_folder = imp.settings.string("download-folder");
-            let download_folder_exists = std::fs::exists(&download_folder).unwrap_or_default();
-
-            if !download_folder_exists {
-                let fallback = xdg_download_with_fallback();
-
-                tracing::warn!(
-                    ?download_folder,
-                    ?fallback,
-                    "Couldn't access Downloads folder. Resetting to fallback"
-                );
-
-                // Fallback for when user doesn't select a download folder when prompted
-                imp.settings
-                    .set_string("download-folder", fallback.to_str().unwrap())
-                    .unwrap();
-
-                imp.toast_overlay.add_toast(
-                    adw::Toast::builder()
-                        .title(&gettext("Can't access Downloads folder"))
-                        .button_label(&gettext("Pick Folder"))
-                        .action_name("win.pick-download-folder")
-                        .build(),
-                );
-            }
-        }
-
         imp.download_folder_row.set_subtitle(
             &strip_user_home_prefix(&imp.settings.string("download-folder")).to_string_lossy(),
         );
@@ -954,9 +1902,912 @@ impl PacketApplicationWindow {
                 imp.obj().pick_download_folder();
             }
         ));
-    }
 
-    async fn portal_request_background(&self) -> Option<Background> {
+        self.update_text_save_folder_row();
+        for key in ["text-save-folder", "download-folder"] {
+            imp.settings.connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_, _| {
+                        this.update_text_save_folder_row();
+                    }
+                ),
+            );
+        }
+        imp.text_save_folder_pick_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    this,
+                    async move {
+                        if let Ok(folder) = gtk::FileDialog::builder()
+                            .initial_folder(&gio::File::for_path(this.text_save_folder()))
+                            .build()
+                            .select_folder_future(Some(&this))
+                            .await
+                            && let Some(folder_path) = folder.path()
+                        {
+                            tracing::debug!(?folder_path, "Selected text save folder");
+                            this.imp()
+                                .settings
+                                .set_string("text-save-folder", &folder_path.to_string_lossy())
+                                .unwrap();
+                        }
+                    }
+                ));
+            }
+        ));
+        imp.text_save_folder_clear_button.connect_clicked(clone!(
+            #[weak]
+            imp,
+            move |_| {
+                imp.settings.reset("text-save-folder");
+            }
+        ));
+
+        imp.reset_settings_button_row.connect_activated(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.present_reset_settings_dialog();
+            }
+        ));
+
+        imp.clear_history_button_row.connect_activated(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.present_clear_history_dialog();
+            }
+        ));
+
+        self.refresh_trusted_device_rows();
+        imp.trusted_device_entry.connect_apply(clone!(
+            #[weak]
+            imp,
+            move |entry| {
+                let device_name = entry.text().trim().to_string();
+                if device_name.is_empty() || imp.obj().is_trusted_device(&device_name) {
+                    return;
+                }
+
+                let mut device_names = imp
+                    .settings
+                    .strv("trusted-devices")
+                    .iter()
+                    .map(|it| it.to_string())
+                    .collect::<Vec<_>>();
+                device_names.push(device_name);
+                imp.obj().set_trusted_devices(&device_names);
+
+                entry.set_text("");
+                imp.obj().refresh_trusted_device_rows();
+            }
+        ));
+
+        self.refresh_blocked_extension_rows();
+        imp.blocked_extension_entry.connect_apply(clone!(
+            #[weak]
+            imp,
+            move |entry| {
+                // Taken both with and without the leading dot
+                let extension = entry.text().trim().trim_start_matches('.').to_lowercase();
+                if extension.is_empty()
+                    || extension
+                        .chars()
+                        .any(|it| it.is_whitespace() || std::path::is_separator(it))
+                {
+                    entry.add_css_class("error");
+                    return;
+                }
+                entry.remove_css_class("error");
+
+                let mut extensions = imp
+                    .settings
+                    .strv("blocked-extensions")
+                    .iter()
+                    .map(|it| it.to_string())
+                    .collect::<Vec<_>>();
+                if !extensions.contains(&extension) {
+                    extensions.push(extension);
+                    imp.obj().set_blocked_extensions(&extensions);
+                }
+
+                entry.set_text("");
+                imp.obj().refresh_blocked_extension_rows();
+            }
+        ));
+
+        self.refresh_per_device_folder_rows();
+        imp.per_device_folder_entry.connect_apply(clone!(
+            #[weak]
+            imp,
+            move |entry| {
+                let device_name = entry.text().trim().to_string();
+                if !device_name.is_empty() {
+                    imp.obj().pick_per_device_download_folder(device_name);
+                }
+            }
+        ));
+    }
+
+    fn present_reset_settings_dialog(&self) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Reset All Settings?"))
+            .body(&gettext(
+                "All preferences will be restored to their defaults, and \
+                file manager plugins will be removed.",
+            ))
+            .default_response("cancel")
+            .close_response("cancel")
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("Cancel")), ("reset", &gettext("Reset"))]);
+        dialog.set_response_appearance("reset", adw::ResponseAppearance::Destructive);
+        dialog.connect_response(
+            Some("reset"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    this.reset_settings();
+                }
+            ),
+        );
+
+        dialog.present(self.root().as_ref());
+    }
+
+    fn present_clear_history_dialog(&self) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Clear History?"))
+            .body(&gettext(
+                "Recent recipients and how often files were sent to them will be forgotten.",
+            ))
+            .default_response("cancel")
+            .close_response("cancel")
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("Cancel")), ("clear", &gettext("Clear"))]);
+        dialog.set_response_appearance("clear", adw::ResponseAppearance::Destructive);
+        dialog.connect_response(
+            Some("clear"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    tracing::info!("Clearing recipient history");
+                    this.imp().settings.reset("recipient-history");
+                    this.add_toast(&gettext("Cleared history"));
+                }
+            ),
+        );
+
+        dialog.present(self.root().as_ref());
+    }
+
+    /// Resets the preferences and brings the running state in line with them.
+    ///
+    /// Switches that have side effects, like the plugins, the tray icon, and
+    /// running in background, are bound to their keys and take care of
+    /// themselves as their handlers run.
+    fn reset_settings(&self) {
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                let imp = this.imp();
+
+                // Restarting the service would abort them
+                if !this.is_no_file_being_send() || !this.is_no_file_being_received().await {
+                    this.add_toast(&gettext("Can't reset settings during a transfer"));
+                    return;
+                }
+
+                tracing::info!("Resetting all settings");
+
+                this.stop_temporary_visibility();
+                for key in RESETTABLE_SETTINGS {
+                    imp.settings.reset(key);
+                }
+
+                // Fill in the defaults that depend on the system, same as on startup
+                imp.settings
+                    .set_string(
+                        "download-folder",
+                        xdg_download_with_fallback().to_str().unwrap(),
+                    )
+                    .unwrap();
+                let device_name = whoami::devicename();
+                imp.device_name_entry.set_text(&device_name);
+                this.set_device_name_state(&device_name).unwrap();
+
+                imp.static_port_entry
+                    .set_text(&imp.settings.int("static-port-number").to_string());
+                imp.download_folder_row.set_subtitle(
+                    &strip_user_home_prefix(&imp.settings.string("download-folder"))
+                        .to_string_lossy(),
+                );
+                this.refresh_per_device_folder_rows();
+                this.refresh_trusted_device_rows();
+                this.refresh_blocked_extension_rows();
+
+                _ = this.restart_rqs_service().await;
+                this.start_mdns_discovery(Some(imp.is_mdns_discovery_on.get()));
+
+                this.add_toast(&gettext("Settings were reset"));
+            }
+        ));
+    }
+
+    /// Mapping of sender device names to the folder their files should be
+    /// saved in, overriding the global download folder.
+    fn per_device_download_folders(&self) -> BTreeMap<String, String> {
+        let value = self.imp().settings.string("per-device-download-folders");
+        utils::json_or_default(&value, "per-device download folders")
+    }
+
+    fn set_per_device_download_folders(&self, folders: &BTreeMap<String, String>) {
+        // A map of strings always serializes
+        let value = serde_json::to_string(folders).unwrap();
+        self.imp()
+            .settings
+            .set_string("per-device-download-folders", &value)
+            .unwrap();
+    }
+
+    /// Returns the folder files from `device_name` should be received into,
+    /// falling back to the global download folder.
+    pub fn download_folder_for_device(&self, device_name: &str) -> PathBuf {
+        self.per_device_download_folders()
+            .remove(device_name)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(self.imp().settings.string("download-folder")))
+    }
+
+    fn refresh_per_device_folder_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.per_device_folder_rows.take() {
+            imp.per_device_folders_group.remove(&row);
+        }
+
+        let rows = self
+            .per_device_download_folders()
+            .into_iter()
+            .map(|(device_name, folder)| {
+                let row = adw::ActionRow::builder()
+                    .title(&device_name)
+                    .subtitle(&*strip_user_home_prefix(&folder).to_string_lossy())
+                    .use_markup(false)
+                    .build();
+
+                let remove_button = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .icon_name("user-trash-symbolic")
+                    .tooltip_text(&gettext("Remove"))
+                    .css_classes(["flat"])
+                    .build();
+                remove_button.connect_clicked(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| {
+                        let mut folders = this.per_device_download_folders();
+                        folders.remove(&device_name);
+                        this.set_per_device_download_folders(&folders);
+                        this.refresh_per_device_folder_rows();
+                    }
+                ));
+                row.add_suffix(&remove_button);
+
+                imp.per_device_folders_group.add(&row);
+                row
+            })
+            .collect();
+        imp.per_device_folder_rows.replace(rows);
+    }
+
+    fn pick_per_device_download_folder(&self, device_name: String) {
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                let Ok(file) = gtk::FileDialog::new()
+                    .select_folder_future(Some(&this))
+                    .await
+                else {
+                    return;
+                };
+                let folder_path = file.path().unwrap();
+
+                tracing::debug!(?device_name, ?folder_path, "Selected per-device folder");
+
+                let mut folders = this.per_device_download_folders();
+                folders.insert(device_name, folder_path.to_string_lossy().into_owned());
+                this.set_per_device_download_folders(&folders);
+
+                this.imp().per_device_folder_entry.set_text("");
+                this.refresh_per_device_folder_rows();
+            }
+        ));
+    }
+
+    pub fn recipient_history(&self) -> BTreeMap<String, objects::RecipientHistoryEntry> {
+        let value = self.imp().settings.string("recipient-history");
+        utils::json_or_default(&value, "recipient history")
+    }
+
+    /// Bumps the send count and last sent time of the recipient.
+    pub fn record_sent_to_recipient(&self, device_name: &str) {
+        let mut history = self.recipient_history();
+        let entry = history.entry(device_name.to_string()).or_default();
+        entry.send_count += 1;
+        entry.last_sent = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        self.set_recipient_history(&history);
+    }
+
+    /// GSettings replaces the stored value as a whole, so the history can't be
+    /// left half written if the app is killed meanwhile.
+    fn set_recipient_history(&self, history: &BTreeMap<String, objects::RecipientHistoryEntry>) {
+        // A map of plain structs always serializes
+        let value = serde_json::to_string(history).unwrap();
+        _ = self
+            .imp()
+            .settings
+            .set_string("recipient-history", &value)
+            .inspect_err(|err| tracing::warn!("{err:#}"));
+    }
+
+    /// Drops the recipients last sent to longer ago than `history-retention-days`.
+    fn prune_recipient_history(&self) {
+        let retention_days = self.imp().settings.int("history-retention-days") as u64;
+        if retention_days == 0 {
+            return;
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let cutoff = now.saturating_sub(retention_days * 24 * 60 * 60);
+
+        let mut history = self.recipient_history();
+        let len = history.len();
+        history.retain(|_, entry| entry.last_sent >= cutoff);
+        if history.len() == len {
+            return;
+        }
+
+        tracing::info!(
+            pruned = len - history.len(),
+            retention_days,
+            "Pruning recipient history"
+        );
+        self.set_recipient_history(&history);
+    }
+
+    pub fn is_pinned_recipient(&self, device_name: &str) -> bool {
+        self.imp()
+            .settings
+            .strv("pinned-recipients")
+            .iter()
+            .any(|it| it.as_str() == device_name)
+    }
+
+    pub fn set_recipient_pinned(&self, device_name: &str, is_pinned: bool) {
+        let settings = &self.imp().settings;
+
+        let mut device_names = settings
+            .strv("pinned-recipients")
+            .iter()
+            .map(|it| it.to_string())
+            .filter(|it| it != device_name)
+            .collect::<Vec<_>>();
+        if is_pinned {
+            device_names.push(device_name.into());
+        }
+
+        tracing::debug!(?device_name, is_pinned, "Setting pinned recipient");
+        settings
+            .set_strv("pinned-recipients", &device_names)
+            .unwrap();
+    }
+
+    /// Lists the pinned recipients as unavailable until they're discovered.
+    fn add_pinned_recipient_placeholders(&self) {
+        let imp = self.imp();
+
+        // Devices might've already been found ahead of time
+        let discovered_names = imp
+            .recipient_model
+            .iter::<SendRequestState>()
+            .filter_map(|it| it.ok())
+            .map(|it| it.endpoint_info().name.clone())
+            .collect::<Vec<_>>();
+
+        for device_name in imp.settings.strv("pinned-recipients").iter() {
+            if discovered_names
+                .iter()
+                .any(|it| it.as_deref() == Some(device_name.as_str()))
+            {
+                continue;
+            }
+
+            let obj = SendRequestState::new();
+            obj.set_endpoint_info(objects::EndpointInfo::pinned_placeholder(
+                device_name.as_str(),
+            ));
+            imp.recipient_model.append(&obj);
+        }
+    }
+
+    fn refresh_recent_recipient_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.recent_recipient_rows.take() {
+            imp.recent_recipients_group.remove(&row);
+        }
+
+        let mut history = self.recipient_history().into_iter().collect::<Vec<_>>();
+        history.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_sent));
+
+        let rows = history
+            .into_iter()
+            .take(RECENT_RECIPIENTS_MAX)
+            .map(|(device_name, entry)| {
+                let row = adw::ActionRow::builder()
+                    .title(&utils::sanitize_device_name(&device_name))
+                    .subtitle(&widgets::format_recipient_history(&entry))
+                    .use_markup(false)
+                    .build();
+
+                let send_again_button = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .label(&gettext("Send Again"))
+                    .css_classes(["flat"])
+                    .build();
+                send_again_button.connect_clicked(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| {
+                        this.send_again_to_recipient(device_name.clone());
+                    }
+                ));
+                row.add_suffix(&send_again_button);
+
+                imp.recent_recipients_group.add(&row);
+                row
+            })
+            .collect::<Vec<_>>();
+        imp.recent_recipients_group.set_visible(!rows.is_empty());
+        imp.recent_recipient_rows.replace(rows);
+    }
+
+    /// Opens the recipients dialog and focuses the previous recipient's card
+    /// once it's discovered. Endpoint IDs don't carry over between discoveries,
+    /// so it goes by the name.
+    fn send_again_to_recipient(&self, device_name: String) {
+        let imp = self.imp();
+
+        tracing::info!(device_name, "Looking for previous recipient");
+        self.present_recipients_dialog();
+
+        glib::spawn_future_local(clone!(
+            #[weak]
+            imp,
+            async move {
+                let deadline = std::time::Instant::now() + RECENT_RECIPIENT_DISCOVERY_TIMEOUT;
+                loop {
+                    if !imp.is_recipients_dialog_opened.get() {
+                        return;
+                    }
+
+                    if widgets::focus_recipient_card(&imp.obj(), &device_name) {
+                        return;
+                    }
+
+                    if std::time::Instant::now() >= deadline {
+                        imp.obj().add_toast(
+                            &formatx!(
+                                gettext("{} isn't available right now"),
+                                utils::sanitize_device_name(&device_name)
+                            )
+                            .unwrap_or_else(|_| "badly formatted locale string".into()),
+                        );
+                        return;
+                    }
+
+                    futures_timer::Delay::new(std::time::Duration::from_millis(500)).await;
+                }
+            }
+        ));
+    }
+
+    /// The response to give an incoming request on its own, as per the first
+    /// rule of the schedule covering the current local time.
+    pub fn scheduled_auto_response(&self) -> Option<UserAction> {
+        let value = self.imp().settings.string("auto-response-schedule");
+        let rules: Vec<objects::AutoResponseRule> =
+            utils::json_or_default(&value, "auto response schedule");
+        if rules.is_empty() {
+            return None;
+        }
+
+        let now = glib::DateTime::now_local()
+            .inspect_err(|err| tracing::warn!(%err, "Couldn't get the local time"))
+            .ok()?;
+        let (weekday, minute_of_day) = (
+            now.day_of_week() as u8,
+            (now.hour() * 60 + now.minute()) as u32,
+        );
+
+        let Some((rule_idx, rule)) = rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(weekday, minute_of_day))
+        else {
+            tracing::debug!(
+                weekday,
+                minute_of_day,
+                "No auto response schedule rule matched"
+            );
+            return None;
+        };
+
+        tracing::info!(rule_idx, ?rule, "Auto response schedule rule matched");
+        Some(rule.action.into())
+    }
+
+    /// Whether the device is invisible but still receiving from trusted
+    /// devices, rqs_lib itself has no notion of this and is just invisible.
+    pub fn is_trusted_only(&self) -> bool {
+        let imp = self.imp();
+        !imp.settings.boolean("device-visibility") && imp.settings.boolean("trusted-only")
+    }
+
+    /// One of "visible", "trusted-only" or "invisible", with the latter two
+    /// being invisible as far as rqs_lib is concerned.
+    fn visibility_mode(&self) -> &'static str {
+        let imp = self.imp();
+        if imp.settings.boolean("device-visibility") {
+            "visible"
+        } else if imp.settings.boolean("trusted-only") {
+            "trusted-only"
+        } else {
+            "invisible"
+        }
+    }
+
+    fn set_visibility_mode(&self, mode: &str) {
+        let imp = self.imp();
+
+        tracing::info!(mode, "Setting visibility mode");
+        let is_visible = match mode {
+            "visible" => true,
+            "trusted-only" => {
+                _ = imp.settings.set_boolean("trusted-only", true);
+                false
+            }
+            "invisible" => {
+                _ = imp.settings.set_boolean("trusted-only", false);
+                false
+            }
+            _ => {
+                tracing::warn!(mode, "Unknown visibility mode");
+                return;
+            }
+        };
+
+        // Goes through the switch's handler to apply it to rqs_lib
+        imp.device_visibility_switch.set_active(is_visible);
+        self.update_visibility_mode_action();
+    }
+
+    fn update_visibility_mode_action(&self) {
+        if let Some(action) = self
+            .lookup_action("visibility-mode")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_state(&self.visibility_mode().to_variant());
+        }
+    }
+
+    /// Whether requests from the trusted device can be accepted without asking,
+    /// see `confirmed_trusted_devices`.
+    pub fn is_confirmed_trusted_device(&self, device_name: &str) -> bool {
+        self.is_trusted_device(device_name)
+            && self
+                .imp()
+                .confirmed_trusted_devices
+                .borrow()
+                .contains(device_name)
+    }
+
+    pub fn confirm_trusted_device(&self, device_name: &str) {
+        if self.is_trusted_device(device_name) {
+            self.imp()
+                .confirmed_trusted_devices
+                .borrow_mut()
+                .insert(device_name.to_string());
+        }
+    }
+
+    pub fn is_trusted_device(&self, device_name: &str) -> bool {
+        self.imp()
+            .settings
+            .strv("trusted-devices")
+            .iter()
+            .any(|it| it.as_str() == device_name)
+    }
+
+    fn set_trusted_devices(&self, device_names: &[String]) {
+        self.imp()
+            .settings
+            .set_strv("trusted-devices", device_names)
+            .unwrap();
+    }
+
+    fn refresh_trusted_device_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.trusted_device_rows.take() {
+            imp.trusted_devices_group.remove(&row);
+        }
+
+        let rows = imp
+            .settings
+            .strv("trusted-devices")
+            .iter()
+            .map(|device_name| {
+                let device_name = device_name.to_string();
+                let row = adw::ActionRow::builder()
+                    .title(&device_name)
+                    .use_markup(false)
+                    .build();
+
+                let remove_button = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .icon_name("user-trash-symbolic")
+                    .tooltip_text(&gettext("Remove"))
+                    .css_classes(["flat"])
+                    .build();
+                remove_button.connect_clicked(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| {
+                        let device_names = this
+                            .imp()
+                            .settings
+                            .strv("trusted-devices")
+                            .iter()
+                            .map(|it| it.to_string())
+                            .filter(|it| *it != device_name)
+                            .collect::<Vec<_>>();
+                        this.set_trusted_devices(&device_names);
+                        this.refresh_trusted_device_rows();
+                    }
+                ));
+                row.add_suffix(&remove_button);
+
+                imp.trusted_devices_group.add(&row);
+                row
+            })
+            .collect();
+        imp.trusted_device_rows.replace(rows);
+    }
+
+    /// The names of the files with an extension from `blocked-extensions`.
+    pub fn blocked_files(&self, files: &[String]) -> Vec<String> {
+        let extensions = self.imp().settings.strv("blocked-extensions");
+
+        files
+            .iter()
+            .filter_map(|file_name| {
+                let file_name = Path::new(file_name).file_name()?;
+                let extension = Path::new(file_name).extension()?.to_str()?;
+                extensions
+                    .iter()
+                    .any(|it| it.as_str().eq_ignore_ascii_case(extension))
+                    .then(|| file_name.to_string_lossy().into_owned())
+            })
+            .collect()
+    }
+
+    fn set_blocked_extensions(&self, extensions: &[String]) {
+        self.imp()
+            .settings
+            .set_strv("blocked-extensions", extensions)
+            .unwrap();
+    }
+
+    fn refresh_blocked_extension_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.blocked_extension_rows.take() {
+            imp.blocked_extensions_group.remove(&row);
+        }
+
+        let rows = imp
+            .settings
+            .strv("blocked-extensions")
+            .iter()
+            .map(|extension| {
+                let extension = extension.to_string();
+                let row = adw::ActionRow::builder()
+                    .title(format!(".{extension}"))
+                    .use_markup(false)
+                    .build();
+
+                let remove_button = gtk::Button::builder()
+                    .valign(gtk::Align::Center)
+                    .icon_name("user-trash-symbolic")
+                    .tooltip_text(&gettext("Remove"))
+                    .css_classes(["flat"])
+                    .build();
+                remove_button.connect_clicked(clone!(
+                    #[weak(rename_to = this)]
+                    self,
+                    move |_| {
+                        let extensions = this
+                            .imp()
+                            .settings
+                            .strv("blocked-extensions")
+                            .iter()
+                            .map(|it| it.to_string())
+                            .filter(|it| *it != extension)
+                            .collect::<Vec<_>>();
+                        this.set_blocked_extensions(&extensions);
+                        this.refresh_blocked_extension_rows();
+                    }
+                ));
+                row.add_suffix(&remove_button);
+
+                imp.blocked_extensions_group.add(&row);
+                row
+            })
+            .collect();
+        imp.blocked_extension_rows.replace(rows);
+    }
+
+    /// Where received text is saved to by default, the download folder unless a
+    /// separate one is set and still around.
+    pub fn text_save_folder(&self) -> PathBuf {
+        let settings = &self.imp().settings;
+        let text_save_folder = PathBuf::from(settings.string("text-save-folder"));
+        if !text_save_folder.as_os_str().is_empty() && text_save_folder.is_dir() {
+            text_save_folder
+        } else {
+            PathBuf::from(settings.string("download-folder"))
+        }
+    }
+
+    fn update_text_save_folder_row(&self) {
+        let imp = self.imp();
+
+        let is_set = !imp.settings.string("text-save-folder").is_empty();
+        let subtitle = if is_set {
+            strip_user_home_prefix(self.text_save_folder())
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            gettext("Same as the downloads folder")
+        };
+        imp.text_save_folder_row.set_subtitle(&subtitle);
+        imp.text_save_folder_clear_button.set_visible(is_set);
+    }
+
+    /// Points rqs back at the global download folder, undoing a per-device
+    /// override set when accepting a transfer.
+    pub fn restore_download_folder(&self) {
+        let folder_path = PathBuf::from(self.imp().settings.string("download-folder"));
+        if let Some(rqs) = self.imp().rqs.blocking_lock().as_mut() {
+            rqs.set_download_path(Some(folder_path));
+        }
+    }
+
+    /// Hides the window, with the service still running to receive files.
+    fn hide_to_background(&self) {
+        // Hiding works either way, but without the permission the app
+        // might be taken for one that hung and killed
+        if !self.imp().is_background_allowed.get() {
+            tracing::warn!("Running in background without the background permission");
+        }
+
+        tracing::info!("Running Packet in background");
+        self.set_visible(false);
+    }
+
+    fn present_close_action_dialog(&self) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Keep Running?"))
+            .body(&gettext(
+                "Packet can keep running in the background after the window is closed, to receive files",
+            ))
+            .default_response("hide")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("quit", &gettext("Quit")),
+            ("hide", &gettext("Keep Running")),
+        ]);
+        dialog.set_response_appearance("hide", adw::ResponseAppearance::Suggested);
+
+        let remember_check = gtk::CheckButton::builder()
+            .label(&gettext("Remember my choice"))
+            .halign(gtk::Align::Center)
+            .build();
+        dialog.set_extra_child(Some(&remember_check));
+
+        dialog.connect_response(
+            None,
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                #[weak]
+                remember_check,
+                move |_, response| {
+                    let imp = this.imp();
+
+                    if response == "cancel" {
+                        return;
+                    }
+
+                    if remember_check.is_active() {
+                        tracing::info!(response, "Remembering close action");
+                        _ = imp.settings.set_string("close-action", response);
+                    }
+
+                    match response {
+                        "hide" => this.hide_to_background(),
+                        "quit" => {
+                            imp.should_quit.replace(true);
+                            this.close();
+                        }
+                        _ => {}
+                    }
+                }
+            ),
+        );
+
+        dialog.present(Some(self));
+    }
+
+    /// Asked only the first time starting at login is turned on, since it's
+    /// easy to miss that the app will then be launched on its own.
+    async fn confirm_auto_start(&self, parent: &impl IsA<gtk::Widget>) -> bool {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Start at Login?"))
+            .body(&gettext(
+                "Packet will start in the background whenever you log in, ready to receive files. This can be turned off again at any time.",
+            ))
+            .default_response("enable")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("enable", &gettext("Start at Login")),
+        ]);
+        dialog.set_response_appearance("enable", adw::ResponseAppearance::Suggested);
+
+        let (tx, rx) = async_channel::bounded(1);
+        dialog.connect_response(None, move |_, response| {
+            _ = tx.try_send(response == "enable");
+        });
+        dialog.present(Some(parent));
+
+        rx.recv().await.unwrap_or_default()
+    }
+
+    async fn portal_request_background(&self) -> Option<Background> {
         let imp = self.imp();
 
         let response = Background::request()
@@ -981,20 +2832,13 @@ impl PacketApplicationWindow {
                 imp.is_background_allowed.replace(false);
 
                 with_signals_blocked(
-                    &[
-                        (
-                            &imp.run_in_background_switch.get(),
-                            imp.run_in_background_switch_handler_id.borrow().as_ref(),
-                        ),
-                        (
-                            &imp.auto_start_switch.get(),
-                            imp.auto_start_switch_handler_id.borrow().as_ref(),
-                        ),
-                    ],
+                    &[(
+                        &imp.auto_start_switch.get(),
+                        imp.auto_start_switch_handler_id.borrow().as_ref(),
+                    )],
                     || {
                         // Reset preferences to false in case request fails
                         _ = imp.settings.set_boolean("auto-start", false);
-                        _ = imp.settings.set_boolean("run-in-background", false);
                     },
                 );
 
@@ -1008,7 +2852,7 @@ impl PacketApplicationWindow {
             #[weak(rename_to = this)]
             self,
             async move {
-                let is_run_in_background = this.imp().settings.boolean("run-in-background");
+                let is_run_in_background = this.imp().settings.string("close-action") == "hide";
                 if !is_run_in_background {
                     return;
                 }
@@ -1029,6 +2873,96 @@ impl PacketApplicationWindow {
         ));
     }
 
+    /// Offers to save into a new subfolder of the picked one, since not every
+    /// portal's folder picker lets new folders be made.
+    ///
+    /// Returns the folder to use, or `None` if it was cancelled.
+    async fn ask_download_subfolder(&self, picked_path: &std::path::Path) -> Option<PathBuf> {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Use a New Folder?"))
+            .body(
+                &formatx!(
+                    gettext("Received files can be saved into a new folder inside {}"),
+                    strip_user_home_prefix(picked_path).to_string_lossy()
+                )
+                .unwrap_or_else(|_| "badly formatted locale string".into()),
+            )
+            .default_response("create")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("select", &gettext("Use This Folder")),
+            ("create", &gettext("Create Folder")),
+        ]);
+        dialog.set_response_appearance("create", adw::ResponseAppearance::Suggested);
+
+        let listbox = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        let name_row = adw::EntryRow::builder()
+            .title(&gettext("Folder Name"))
+            .text(&gettext("Packet Downloads"))
+            .build();
+        listbox.append(&name_row);
+        dialog.set_extra_child(Some(&listbox));
+
+        name_row.connect_changed(clone!(
+            #[weak]
+            dialog,
+            move |row| {
+                let name = row.text();
+                let name = name.trim();
+                let is_valid = !name.is_empty()
+                    && name != "."
+                    && name != ".."
+                    && !name.chars().any(std::path::is_separator);
+                dialog.set_response_enabled("create", is_valid);
+            }
+        ));
+
+        let (tx, rx) = async_channel::bounded(1);
+        dialog.connect_response(None, move |_, response| {
+            _ = tx.try_send(response.to_string());
+        });
+        dialog.present(Some(self));
+
+        match rx.recv().await.ok()?.as_str() {
+            "select" => Some(picked_path.to_path_buf()),
+            "create" => Some(picked_path.join(name_row.text().trim())),
+            _ => None,
+        }
+    }
+
+    /// Whether there's no incoming transfer underway, so that e.g. the download
+    /// path can be changed without pulling it out from under one.
+    async fn is_no_file_being_received(&self) -> bool {
+        use rqs_lib::TransferState;
+
+        let receive_transfer_cache = self.imp().receive_transfer_cache.lock().await;
+        let Some(event) = receive_transfer_cache
+            .as_ref()
+            .and_then(|it| it.state.event())
+        else {
+            return true;
+        };
+
+        matches!(
+            event
+                .msg
+                .as_client_unchecked()
+                .state
+                .as_ref()
+                .unwrap_or(&TransferState::Initial),
+            TransferState::Initial
+                | TransferState::Disconnected
+                | TransferState::Rejected
+                | TransferState::Cancelled
+                | TransferState::Finished
+        )
+    }
+
     fn pick_download_folder(&self) {
         let imp = self.imp();
 
@@ -1036,6 +2970,23 @@ impl PacketApplicationWindow {
             #[weak]
             imp,
             async move {
+                let refuse_while_receiving = async || {
+                    if imp.obj().is_no_file_being_received().await {
+                        return false;
+                    }
+
+                    tracing::debug!("Active receive found, can't change download folder");
+                    imp.toast_overlay.add_toast(adw::Toast::new(&gettext(
+                        "Can't change the downloads folder while receiving files",
+                    )));
+
+                    true
+                };
+
+                if refuse_while_receiving().await {
+                    return;
+                }
+
                 if let Ok(file) = gtk::FileDialog::new()
                     .select_folder_future(
                         imp.obj()
@@ -1060,7 +3011,38 @@ impl PacketApplicationWindow {
                     // Now, there's an issue with the vscode-flatpak extension where while running
                     // the app through it, the path given by FileChooser is always a mounted path.
                     // Leaving this note here so as to not base our logic on this wrong behaviour.
-                    let folder_path = file.path().unwrap();
+                    let picked_path = file.path().unwrap();
+                    let Some(folder_path) = imp.obj().ask_download_subfolder(&picked_path).await
+                    else {
+                        return;
+                    };
+
+                    // A transfer might've started while the dialogs were open
+                    if refuse_while_receiving().await {
+                        return;
+                    }
+
+                    if folder_path != picked_path {
+                        let created = tokio_runtime()
+                            .spawn_blocking(clone!(
+                                #[strong]
+                                folder_path,
+                                move || fs_err::create_dir_all(folder_path)
+                            ))
+                            .await
+                            .map_err(|err| anyhow!(err))
+                            .and_then(|it| it.map_err(|err| anyhow!(err)));
+                        if let Err(err) = created {
+                            tracing::error!(
+                                ?folder_path,
+                                "Failed to create downloads folder: {err:#}"
+                            );
+                            imp.toast_overlay.add_toast(adw::Toast::new(&gettext(
+                                "Couldn't create the folder, the downloads folder is unchanged",
+                            )));
+                            return;
+                        }
+                    }
 
                     let display_path = strip_user_home_prefix(&folder_path);
 
@@ -1076,22 +3058,15 @@ impl PacketApplicationWindow {
                     imp.settings
                         .set_string("download-folder", folder_path.to_str().unwrap())
                         .unwrap();
-                    imp.rqs
-                        .lock()
-                        .await
-                        .as_mut()
-                        .unwrap()
-                        .set_download_path(Some(folder_path));
+
+                    let service_ops_lock = imp.service_ops_lock.clone();
+                    let rqs = imp.rqs.clone();
+                    set_service_download_path(&service_ops_lock, &rqs, folder_path).await;
                 };
             }
         ));
     }
 
-    #[cfg(target_os = "linux")]
-    /// There's `tray-icon` for cross-platform systray support but on linux it still relies on gtk3 which doesn't
-    /// work with gtk4 environment.
-    ///
-    /// https://github.com/tauri-apps/tray-icon/pull/201
     fn setup_tray_icon(&self) {
         let imp = self.imp();
 
@@ -1102,23 +3077,43 @@ impl PacketApplicationWindow {
         if is_enable_tray_icon {
             self.enable_tray_icon();
         }
+
+        // Every way of changing the visibility ends up going through the
+        // switch bound to this key
+        imp.settings.connect_changed(
+            Some("device-visibility"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    this.update_tray_icon();
+                }
+            ),
+        );
+    }
+
+    fn update_tray_icon(&self) {
+        let imp = self.imp();
+
+        if let Some(tray) = imp.tray_icon_handle.borrow_mut().as_mut() {
+            tray.update(crate::tray::TrayState {
+                is_visible: imp.device_visibility_switch.is_active(),
+            });
+        }
     }
 
-    #[cfg(target_os = "linux")]
     async fn disable_tray_icon(&self) {
         let imp = self.imp();
 
-        if let Some(ref mut handle) = *imp.tray_icon_handle.borrow_mut() {
+        let tray = imp.tray_icon_handle.take();
+        if let Some(mut tray) = tray {
             tracing::debug!("Disabling tray icon");
-            handle.shutdown().await;
+            tray.shutdown().await;
         }
-        imp.tray_icon_handle.take();
     }
 
-    #[cfg(target_os = "linux")]
     fn enable_tray_icon(&self) -> glib::JoinHandle<()> {
-        use crate::tray;
-        use ksni::*;
+        use crate::tray::{self, TrayBackend};
 
         let imp = self.imp();
 
@@ -1128,17 +3123,13 @@ impl PacketApplicationWindow {
             #[weak]
             imp,
             async move {
-                let tray = crate::tray::Tray { tx: tx };
-                let handle = if ashpd::is_sandboxed().await {
-                    tray.spawn_without_dbus_name().await
-                } else {
-                    tray.spawn().await
-                }
-                .inspect_err(
-                    |err| tracing::warn!(%err, "Failed to setup KStatusNotifierItem tray icon"),
-                )
-                .ok();
-                *imp.tray_icon_handle.borrow_mut() = handle;
+                let tray = tray::PlatformTray::spawn(tx)
+                    .await
+                    .inspect_err(|err| tracing::warn!("{err:#}"))
+                    .ok()
+                    .map(|it| Box::new(it) as Box<dyn TrayBackend>);
+                *imp.tray_icon_handle.borrow_mut() = tray;
+                imp.obj().update_tray_icon();
             }
         ));
 
@@ -1151,11 +3142,19 @@ impl PacketApplicationWindow {
                         tray::TrayMessage::OpenWindow => {
                             imp.obj().present();
                         }
+                        tray::TrayMessage::ToggleVisibility => {
+                            imp.device_visibility_switch
+                                .set_active(!imp.device_visibility_switch.is_active());
+                        }
+                        tray::TrayMessage::OpenPreferences => {
+                            imp.obj().present();
+                            _ = imp
+                                .obj()
+                                .activate_action("win.preferences", None)
+                                .inspect_err(|err| tracing::warn!(%err));
+                        }
                         tray::TrayMessage::Quit => {
-                            imp.should_quit.replace(true);
-                            // FIXME: If preference window is opened, that window gets closed instead of
-                            // PacketApplicationWindow for some reason
-                            imp.obj().close();
+                            imp.obj().quit_window();
                         }
                     }
                 }
@@ -1167,11 +3166,190 @@ impl PacketApplicationWindow {
 
     fn setup_ui(&self) {
         self.setup_bottom_bar();
+        self.setup_clipboard_offer();
 
         self.setup_status_pages();
         self.setup_main_page();
         self.setup_manage_files_page();
-        self.setup_recipient_page();
+
+        self.apply_receive_only_mode();
+        self.imp().settings.connect_changed(
+            Some("receive-only-mode"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    this.apply_receive_only_mode();
+                }
+            ),
+        );
+    }
+
+    /// Hides the ways of adding files to send while in receive only mode. The
+    /// recipients page is only set up once it's first needed.
+    fn apply_receive_only_mode(&self) {
+        let imp = self.imp();
+
+        let is_receive_only = imp.settings.boolean("receive-only-mode");
+        tracing::info!(is_receive_only, "Setting receive only mode");
+
+        if is_receive_only {
+            self.close_recipients_dialog();
+            _ = imp.main_nav_view.pop_to_tag("main_nav_page");
+        } else if !imp.is_recipient_page_set_up.replace(true) {
+            self.setup_recipient_page();
+        }
+
+        imp.main_add_files_button.set_visible(!is_receive_only);
+        imp.main_resend_files_button
+            .set_visible(!is_receive_only && !imp.previous_files.borrow().is_empty());
+        for drop_target in imp.send_files_drop_targets.borrow().iter() {
+            drop_target.set_propagation_phase(if is_receive_only {
+                gtk::PropagationPhase::None
+            } else {
+                gtk::PropagationPhase::Bubble
+            });
+        }
+
+        self.update_main_page_description();
+    }
+
+    fn update_main_page_description(&self) {
+        let imp = self.imp();
+
+        let description = if imp.settings.boolean("receive-only-mode") {
+            gettext("Nearby devices can share files with you")
+        } else if imp.main_nav_content.has_css_class("compact") {
+            gettext("Drop a file to send")
+        } else {
+            gettext("Add, or drop a file to send")
+        };
+        imp.main_nav_content.set_description(Some(&description));
+    }
+
+    /// Installs or uninstalls the plugin as the switch is toggled, and updates
+    /// it on startup if it's already enabled.
+    fn setup_plugin_switch<P: Plugin + Clone + Send + 'static>(
+        &self,
+        plugin: P,
+        plugin_name: &'static str,
+        switch: &adw::SwitchRow,
+        switch_handler_id: fn(
+            &imp::PacketApplicationWindow,
+        ) -> &RefCell<Option<glib::SignalHandlerId>>,
+        help_install_dir: &'static str,
+        present_success_dialog: fn(&Self),
+    ) {
+        let imp = self.imp();
+
+        if switch.is_active() {
+            // Update plugin
+            // This takes care of cases of applying updates to the plugin files
+            // as well as reinstalling them if they got removed for some reason.
+            let plugin = plugin.clone();
+            glib::spawn_future_local(clone!(
+                #[weak]
+                imp,
+                async move {
+                    let success = tokio_runtime()
+                        .spawn_blocking(move || plugin.install_plugin())
+                        .await
+                        .map_err(|err| anyhow::anyhow!(err))
+                        .and_then(|it| it.map_err(anyhow::Error::from))
+                        .inspect_err(|err| tracing::error!("{err:#}"))
+                        .is_ok();
+
+                    if !success {
+                        imp.obj().add_toast(
+                            &formatx!(gettext("Couldn't update the {} plugin"), plugin_name)
+                                .unwrap_or_default(),
+                        );
+                    }
+                }
+            ));
+        }
+
+        let _signal_handle = switch.connect_active_notify(clone!(
+            #[weak]
+            imp,
+            move |switch| {
+                let plugin = plugin.clone();
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    imp,
+                    #[weak]
+                    switch,
+                    async move {
+                        switch.set_sensitive(false);
+
+                        let enable_plugin = switch.is_active();
+
+                        tracing::info!(plugin = plugin_name, enable_plugin, "Setting plugin state");
+
+                        let result = tokio_runtime()
+                            .spawn_blocking(move || {
+                                if enable_plugin {
+                                    plugin.install_plugin()
+                                } else {
+                                    plugin.uninstall_plugin()
+                                }
+                            })
+                            .await
+                            .map_err(|err| anyhow::anyhow!(err))
+                            .and_then(|it| it.map_err(anyhow::Error::from))
+                            .inspect_err(|err| tracing::error!("{err:#}"));
+
+                        if enable_plugin {
+                            match result {
+                                Ok(()) => present_success_dialog(&imp.obj()),
+                                Err(err) => {
+                                    imp.obj()
+                                        .present_plugin_install_error(&err, help_install_dir);
+                                    with_signals_blocked(
+                                        &[(&switch, switch_handler_id(&imp).borrow().as_ref())],
+                                        || {
+                                            switch.set_active(false);
+                                        },
+                                    );
+                                }
+                            }
+                        }
+
+                        switch.set_sensitive(true);
+                    }
+                ));
+            }
+        ));
+        switch_handler_id(imp).replace(Some(_signal_handle));
+    }
+
+    /// Shows a warning on the plugin's switch if it's enabled while the Python
+    /// modules it needs are missing, otherwise it'd just do nothing.
+    fn check_nautilus_plugin_deps(&self) {
+        let imp = self.imp();
+
+        if !imp.nautilus_plugin_switch.is_active() {
+            imp.nautilus_plugin_warning_icon.set_visible(false);
+            return;
+        }
+
+        let plugin = imp.nautilus_plugin.clone();
+        glib::spawn_future_local(clone!(
+            #[weak]
+            imp,
+            async move {
+                let result = tokio_runtime()
+                    .spawn_blocking(move || plugin.check_runtime_deps())
+                    .await
+                    .map_err(|err| anyhow::anyhow!(err))
+                    .and_then(|it| it.map_err(anyhow::Error::from))
+                    .inspect_err(|err| tracing::warn!("{err:#}"));
+
+                // The switch might've been turned off in the meantime
+                imp.nautilus_plugin_warning_icon
+                    .set_visible(result.is_err() && imp.nautilus_plugin_switch.is_active());
+            }
+        ));
     }
 
     fn present_plugin_success_dialog(&self) {
@@ -1235,6 +3413,84 @@ impl PacketApplicationWindow {
         dialog.present(self.root().as_ref());
     }
 
+    fn present_plugin_restart_dialog(&self, file_manager_name: &str) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Plugin Installed"))
+            .body(
+                &formatx!(
+                    gettext(
+                        "The plugin was installed successfully. Restart {} to \
+                        load the plugin."
+                    ),
+                    file_manager_name
+                )
+                .unwrap_or_default(),
+            )
+            .default_response("done")
+            .build();
+
+        dialog.add_response("done", &gettext("Done"));
+        dialog.set_response_appearance("done", adw::ResponseAppearance::Suggested);
+
+        dialog.present(self.root().as_ref());
+    }
+
+    /// Picks the dialog that best explains why the plugin couldn't be installed.
+    fn present_plugin_install_error(&self, err: &anyhow::Error, help_install_dir: &str) {
+        let body = match err.downcast_ref::<PluginError>() {
+            Some(PluginError::DepsMissing(_)) => {
+                self.present_plugin_deps_dialog();
+                return;
+            }
+            Some(PluginError::FilesMissing(_)) => gettext(
+                "The plugin's files are missing from this installation of Packet, \
+                try reinstalling it.",
+            ),
+            Some(PluginError::Malformed(path)) => formatx!(
+                gettext(
+                    "The plugin couldn't be added to {} since it isn't in the expected format."
+                ),
+                path.display()
+            )
+            .unwrap_or_else(|_| "badly formatted locale string".into()),
+            Some(
+                PluginError::DirNotFound | PluginError::DirNotWritable(_) | PluginError::Io(_),
+            )
+            | None => {
+                self.present_plugin_error_dialog(help_install_dir);
+                return;
+            }
+        };
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Installation Failed"))
+            .body(&body)
+            .default_response("close")
+            .build();
+        dialog.add_response("close", &gettext("Close"));
+        dialog.set_response_appearance("close", adw::ResponseAppearance::Suggested);
+
+        dialog.present(self.root().as_ref());
+    }
+
+    fn present_plugin_deps_dialog(&self) {
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Missing Packages"))
+            .body(
+                &formatx!(
+                    gettext("The plugin requires the following packages to function: {}"),
+                    "nautilus-python, python-dbus",
+                )
+                .unwrap_or_default(),
+            )
+            .default_response("close")
+            .build();
+        dialog.add_response("close", &gettext("Close"));
+        dialog.set_response_appearance("close", adw::ResponseAppearance::Suggested);
+
+        dialog.present(self.root().as_ref());
+    }
+
     fn present_plugin_error_dialog(&self, extensions_display_dir: &str) {
         let dialog = adw::AlertDialog::builder()
             .heading(&gettext("Installation Failed"))
@@ -1270,29 +3526,255 @@ impl PacketApplicationWindow {
             .build();
         info_box.append(&extensions_dir_label);
 
-        dialog.present(self.root().as_ref());
+        dialog.present(self.root().as_ref());
+    }
+
+    pub async fn spawn_send_files_receiver(&self, rx: async_channel::Receiver<Vec<String>>) {
+        let imp = self.imp();
+
+        while let Ok(mut files) = rx.recv().await {
+            // Present the dialog once for batches arriving in quick succession,
+            // each one pushing the deadline further
+            loop {
+                tokio::select! {
+                    batch = rx.recv() => match batch {
+                        Ok(batch) => files.extend(batch),
+                        Err(_) => break,
+                    },
+                    _ = futures_timer::Delay::new(SEND_FILES_COALESCE_WINDOW) => break,
+                }
+            }
+
+            // The same file might be sent from more than one invocation
+            let mut seen = std::collections::HashSet::new();
+            files.retain(|it| seen.insert(it.clone()));
+            tracing::debug!(count = files.len(), "Coalesced files to send");
+
+            // Bring the app window to focus
+            self.present();
+
+            if imp.settings.boolean("receive-only-mode") {
+                tracing::info!("Ignoring files to send in receive only mode");
+                self.add_toast(&gettext("Sending is turned off in receive only mode"));
+                continue;
+            }
+
+            let success = self.handle_added_files_to_send(
+                &imp.manage_files_model,
+                files
+                    .into_iter()
+                    .map(|it| gio::File::for_path(it))
+                    .collect::<Vec<_>>(),
+            );
+            if success {
+                self.present_recipients_dialog();
+            } else {
+                self.close_recipients_dialog();
+            }
+        }
+    }
+
+    /// Replaces the selected files with these and goes straight to picking the
+    /// recipients.
+    pub fn send_files(&self, files: Vec<gio::File>) {
+        let imp = self.imp();
+
+        imp.manage_files_model.remove_all();
+        if self.handle_added_files_to_send(&imp.manage_files_model, files) {
+            self.present_recipients_dialog();
+        }
+    }
+
+    /// Sends the files copied to the clipboard, the same as if they were
+    /// dropped onto the window.
+    fn send_clipboard(&self) {
+        let imp = self.imp();
+
+        if imp.settings.boolean("receive-only-mode") {
+            self.add_toast(&gettext("Sending is turned off in receive only mode"));
+            return;
+        }
+
+        let clipboard = self.clipboard();
+        let formats = clipboard.formats();
+        if formats.contain_gtype(gdk::FileList::static_type()) {
+            glib::spawn_future_local(clone!(
+                #[weak(rename_to = this)]
+                self,
+                async move {
+                    let file_list = clipboard
+                        .read_value_future(gdk::FileList::static_type(), glib::Priority::DEFAULT)
+                        .await
+                        .inspect_err(|err| tracing::warn!(%err, "Couldn't read the clipboard"))
+                        .ok()
+                        .and_then(|it| it.get::<gdk::FileList>().ok());
+
+                    match file_list {
+                        Some(file_list) => {
+                            let imp = this.imp();
+                            imp.manage_files_model.remove_all();
+                            this.handle_added_files_to_send(
+                                &imp.manage_files_model,
+                                file_list.files(),
+                            );
+                        }
+                        None => this.add_toast(&gettext("Couldn't read the clipboard")),
+                    }
+                }
+            ));
+        } else if formats.contain_gtype(glib::types::Type::STRING) {
+            // TODO: rqs_lib can only send files so far, `OutboundPayload` has no
+            // text variant to route this through
+            tracing::info!("Sending text from the clipboard isn't supported");
+            self.add_toast(&gettext("Sending text isn't supported yet"));
+        } else {
+            self.add_toast(&gettext("Nothing to send in the clipboard"));
+        }
+    }
+
+    /// With `watch-clipboard`, offers to send files as soon as they're copied,
+    /// until the offer times out or something else gets copied.
+    fn setup_clipboard_offer(&self) {
+        let imp = self.imp();
+
+        imp.clipboard_offer_send_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                let files = this.imp().clipboard_offer_files.take();
+                this.dismiss_clipboard_offer();
+                this.send_files(files);
+            }
+        ));
+        imp.clipboard_offer_dismiss_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.dismiss_clipboard_offer();
+            }
+        ));
+
+        self.clipboard().connect_changed(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |clipboard| {
+                this.dismiss_clipboard_offer();
+
+                let imp = this.imp();
+                if !imp.settings.boolean("watch-clipboard")
+                    || imp.settings.boolean("receive-only-mode")
+                    // e.g. copying the device name
+                    || clipboard.is_local()
+                    || !clipboard
+                        .formats()
+                        .contain_gtype(gdk::FileList::static_type())
+                {
+                    return;
+                }
+
+                let ctk = CancellationToken::new();
+                imp.clipboard_offer_ctk.replace(Some(ctk.clone()));
+
+                glib::spawn_future_local(clone!(
+                    #[weak]
+                    this,
+                    #[weak]
+                    clipboard,
+                    async move {
+                        let files = tokio::select! {
+                            file_list = clipboard.read_value_future(
+                                gdk::FileList::static_type(),
+                                glib::Priority::DEFAULT,
+                            ) => {
+                                file_list
+                                    .inspect_err(|err| tracing::warn!(%err, "Couldn't read the clipboard"))
+                                    .ok()
+                                    .and_then(|it| it.get::<gdk::FileList>().ok())
+                                    .map(|it| it.files())
+                                    .unwrap_or_default()
+                            }
+                            _ = ctk.cancelled() => return,
+                        };
+                        if files.is_empty() {
+                            return;
+                        }
+
+                        tracing::debug!(count = files.len(), "Offering to send copied files");
+                        let imp = this.imp();
+                        imp.clipboard_offer_label.set_label(
+                            &formatx!(
+                                ngettext(
+                                    "Send {} copied file?",
+                                    "Send {} copied files?",
+                                    files.len() as u32
+                                ),
+                                files.len()
+                            )
+                            .unwrap_or_else(|_| "badly formatted locale string".into()),
+                        );
+                        imp.clipboard_offer_files.replace(files);
+                        imp.clipboard_offer_revealer.set_reveal_child(true);
+
+                        tokio::select! {
+                            _ = futures_timer::Delay::new(CLIPBOARD_OFFER_TIMEOUT) => {
+                                this.dismiss_clipboard_offer();
+                            }
+                            _ = ctk.cancelled() => {}
+                        }
+                    }
+                ));
+            }
+        ));
     }
 
-    pub async fn spawn_send_files_receiver(&self, rx: async_channel::Receiver<Vec<String>>) {
+    fn dismiss_clipboard_offer(&self) {
         let imp = self.imp();
 
-        while let Ok(files) = rx.recv().await {
-            // Bring the app window to focus
-            self.present();
+        if let Some(ctk) = imp.clipboard_offer_ctk.take() {
+            ctk.cancel();
+        }
+        imp.clipboard_offer_files.take();
+        imp.clipboard_offer_revealer.set_reveal_child(false);
+    }
 
-            let success = self.handle_added_files_to_send(
-                &imp.manage_files_model,
-                files
-                    .into_iter()
-                    .map(|it| gio::File::for_path(it))
-                    .collect::<Vec<_>>(),
-            );
-            if success {
-                self.present_recipients_dialog();
-            } else {
-                self.close_recipients_dialog();
-            }
+    /// Sending to many devices can take a long while, so it's confirmed first.
+    fn present_send_to_all_dialog(&self) {
+        let recipient_count = widgets::idle_recipients(self).len();
+        if recipient_count == 0 {
+            return;
         }
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(&gettext("Send to All?"))
+            .body(
+                &formatx!(
+                    ngettext(
+                        "The files will be sent to {} device, one after another",
+                        "The files will be sent to {} devices, one after another",
+                        recipient_count as u32
+                    ),
+                    recipient_count
+                )
+                .unwrap_or_else(|_| "badly formatted locale string".into()),
+            )
+            .default_response("send")
+            .close_response("cancel")
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("Cancel")), ("send", &gettext("Send"))]);
+        dialog.set_response_appearance("send", adw::ResponseAppearance::Suggested);
+        dialog.connect_response(
+            Some("send"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    widgets::send_to_all_recipients(&this);
+                }
+            ),
+        );
+
+        dialog.present(self.root().as_ref());
     }
 
     fn present_recipients_dialog(&self) {
@@ -1303,8 +3785,27 @@ impl PacketApplicationWindow {
         }
 
         // Clear previous recipients
-        imp.send_transfers_id_cache.blocking_lock().clear();
-        imp.recipient_model.remove_all();
+        imp.send_queue.borrow_mut().clear();
+        imp.send_all_recipients.borrow_mut().clear();
+        {
+            let mut send_transfers_id_cache = imp.send_transfers_id_cache.blocking_lock();
+            if imp.is_mdns_discovery_on.get() {
+                // Keeping the devices that were found ahead of time, that's
+                // the whole point of discovering early
+                send_transfers_id_cache.retain(|_, it| {
+                    it.endpoint_info().present.is_some()
+                        && it.transfer_state() == TransferState::AwaitingConsentOrIdle
+                });
+            } else {
+                send_transfers_id_cache.clear();
+            }
+
+            imp.recipient_model.remove_all();
+            for obj in send_transfers_id_cache.values() {
+                imp.recipient_model.append(obj);
+            }
+        }
+        self.add_pinned_recipient_placeholders();
 
         imp.obj().start_mdns_discovery(None);
 
@@ -1375,6 +3876,18 @@ impl PacketApplicationWindow {
                 this.restart_rqs_service();
             }
         ));
+        imp.rqs_error_dynamic_port_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                tracing::info!("Switching to a dynamic port");
+                this.imp()
+                    .settings
+                    .set_boolean("enable-static-port", false)
+                    .unwrap();
+                this.restart_rqs_service();
+            }
+        ));
     }
 
     fn setup_main_page(&self) {
@@ -1388,6 +3901,29 @@ impl PacketApplicationWindow {
                 imp.obj().add_files_via_dialog();
             }
         ));
+        imp.main_resend_files_button.connect_clicked(clone!(
+            #[weak]
+            imp,
+            move |_| {
+                let files = imp
+                    .previous_files
+                    .borrow()
+                    .iter()
+                    .filter(|file| {
+                        let exists = file.query_exists(None::<&gio::Cancellable>);
+                        if !exists {
+                            tracing::warn!(path = ?file.path(), "Previous file no longer exists");
+                        }
+                        exists
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                imp.manage_files_model.remove_all();
+                imp.obj()
+                    .handle_added_files_to_send(&imp.manage_files_model, files);
+            }
+        ));
 
         let files_drop_target = gtk::DropTarget::builder()
             .name("add-files-drop-target")
@@ -1397,6 +3933,9 @@ impl PacketApplicationWindow {
         imp.main_nav_content
             .get()
             .add_controller(files_drop_target.clone());
+        imp.send_files_drop_targets
+            .borrow_mut()
+            .push(files_drop_target.clone());
         files_drop_target.connect_drop(clone!(
             #[weak]
             imp,
@@ -1432,6 +3971,18 @@ impl PacketApplicationWindow {
             }
         ));
 
+        self.refresh_recent_recipient_rows();
+        imp.settings.connect_changed(
+            Some("recipient-history"),
+            clone!(
+                #[weak(rename_to = this)]
+                self,
+                move |_, _| {
+                    this.refresh_recent_recipient_rows();
+                }
+            ),
+        );
+
         let manage_files_add_drop_target = gtk::DropTarget::builder()
             .name("manage-files-add-drop-target")
             .actions(gdk::DragAction::COPY)
@@ -1440,6 +3991,9 @@ impl PacketApplicationWindow {
         imp.manage_files_nav_content
             .get()
             .add_controller(manage_files_add_drop_target.clone());
+        imp.send_files_drop_targets
+            .borrow_mut()
+            .push(manage_files_add_drop_target.clone());
         manage_files_add_drop_target.connect_drop(clone!(
             #[weak]
             imp,
@@ -1474,6 +4028,7 @@ impl PacketApplicationWindow {
                     let row = gtk::ListBoxRow::new();
                     row.set_activatable(false);
                     row.set_child(Some(&widget));
+                    widgets::setup_file_row_reordering(&row, &imp.manage_files_model, model_item);
 
                     row.into()
                 }
@@ -1485,7 +4040,31 @@ impl PacketApplicationWindow {
             imp,
             move |_| {
                 imp.is_recipients_dialog_opened.set(false);
-                imp.obj().stop_mdns_discovery();
+                imp.obj().update_eager_discovery();
+
+                // The dialog can't be closed while a send is still going, so
+                // the credentials aren't needed anymore. They're left out of
+                // the files to resend as well, since they're gone from disk.
+                let wifi_credentials_files = imp.wifi_credentials_files.take();
+                let previous_files = imp
+                    .manage_files_model
+                    .iter::<gio::File>()
+                    .filter_map(|it| it.ok())
+                    .filter(|file| {
+                        !wifi_credentials_files
+                            .iter()
+                            .any(|it| file.path().as_deref() == Some(it.path()))
+                    })
+                    .collect::<Vec<_>>();
+                if !wifi_credentials_files.is_empty() {
+                    tokio_runtime().spawn_blocking(move || drop(wifi_credentials_files));
+                }
+
+                if !previous_files.is_empty() {
+                    imp.previous_files.replace(previous_files);
+                    imp.main_resend_files_button
+                        .set_visible(!imp.settings.boolean("receive-only-mode"));
+                }
             }
         ));
     }
@@ -1493,8 +4072,55 @@ impl PacketApplicationWindow {
     fn setup_recipient_page(&self) {
         let imp = self.imp();
 
+        // Pinned devices first, then the available ones, then alphabetically
+        let recipient_sorter = gtk::CustomSorter::new(clone!(
+            #[weak(rename_to = this)]
+            self,
+            #[upgrade_or]
+            gtk::Ordering::Equal,
+            move |a, b| {
+                let a = a
+                    .downcast_ref::<SendRequestState>()
+                    .unwrap()
+                    .endpoint_info();
+                let b = b
+                    .downcast_ref::<SendRequestState>()
+                    .unwrap()
+                    .endpoint_info();
+
+                let pinned_recipients = this.imp().settings.strv("pinned-recipients");
+                let is_pinned = |name: Option<&str>| {
+                    name.is_some_and(|name| pinned_recipients.iter().any(|it| it.as_str() == name))
+                };
+
+                is_pinned(b.name.as_deref())
+                    .cmp(&is_pinned(a.name.as_deref()))
+                    .then_with(|| b.present.is_some().cmp(&a.present.is_some()))
+                    .then_with(|| {
+                        let a_name = a.name.as_deref().unwrap_or_default().to_lowercase();
+                        let b_name = b.name.as_deref().unwrap_or_default().to_lowercase();
+                        a_name.cmp(&b_name)
+                    })
+                    .into()
+            }
+        ));
+        imp.recipient_sorted_model
+            .set_model(Some(&imp.recipient_model));
+        imp.recipient_sorted_model
+            .set_sorter(Some(&recipient_sorter));
+        imp.settings.connect_changed(
+            Some("pinned-recipients"),
+            clone!(
+                #[weak]
+                recipient_sorter,
+                move |_, _| {
+                    recipient_sorter.changed(gtk::SorterChange::Different);
+                }
+            ),
+        );
+
         imp.recipient_listbox.bind_model(
-            Some(&imp.recipient_model),
+            Some(&imp.recipient_sorted_model),
             clone!(
                 #[weak]
                 imp,
@@ -1522,16 +4148,49 @@ impl PacketApplicationWindow {
         imp.recipient_model.connect_items_changed(clone!(
             #[weak]
             imp,
-            move |model, _, _, _| {
-                if model.n_items() == 0 {
-                    imp.loading_recipients_box.set_visible(true);
-                    imp.recipients_help_button.set_visible(true);
-                    imp.recipient_listbox.set_visible(false);
-                } else {
-                    imp.loading_recipients_box.set_visible(false);
-                    imp.recipients_help_button.set_visible(false);
-                    imp.recipient_listbox.set_visible(true);
+            move |_, _, _, _| {
+                imp.obj().update_recipients_placeholder();
+                widgets::update_recipients_header_buttons(&imp.obj());
+            }
+        ));
+
+        imp.manual_recipient_entry.connect_changed(|entry| {
+            let text = entry.text();
+            if text.is_empty() || parse_manual_recipient_address(&text).is_some() {
+                entry.remove_css_class("error");
+            } else {
+                entry.add_css_class("error");
+            }
+        });
+        imp.manual_recipient_entry.connect_apply(clone!(
+            #[weak]
+            imp,
+            move |entry| {
+                let Some(addr) = parse_manual_recipient_address(&entry.text()) else {
+                    imp.obj().add_toast(&gettext("Invalid address"));
+                    return;
+                };
+
+                tracing::info!(%addr, "Sending to manually entered address");
+                widgets::send_to_manual_recipient(&imp.obj(), addr);
+                entry.set_text("");
+            }
+        ));
+
+        imp.select_recipient_retry_failed_button
+            .connect_clicked(clone!(
+                #[weak]
+                imp,
+                move |_| {
+                    tracing::info!("Retrying failed transfers");
+                    widgets::retry_failed_sends(&imp.obj());
                 }
+            ));
+        imp.select_recipient_send_all_button.connect_clicked(clone!(
+            #[weak]
+            imp,
+            move |_| {
+                imp.obj().present_send_to_all_dialog();
             }
         ));
 
@@ -1561,75 +4220,156 @@ impl PacketApplicationWindow {
             move |_| {
                 tracing::info!("Refreshing recipients");
 
-                {
-                    let mut recipients_to_remove = imp
-                        .recipient_model
-                        .iter::<SendRequestState>()
-                        .enumerate()
-                        .filter_map(|(pos, it)| it.ok().and_then(|it| Some((pos, it))))
-                        .filter(|(_, it)| match it.transfer_state() {
-                            TransferState::Queued
-                            | TransferState::RequestedForConsent
-                            | TransferState::OngoingTransfer => false,
-                            TransferState::AwaitingConsentOrIdle
-                            | TransferState::Failed
-                            | TransferState::Done => true,
-                        })
-                        .collect::<Vec<_>>();
-                    recipients_to_remove.sort_by_key(|(pos, _)| *pos);
-
-                    let mut items_removed = 0;
-                    let mut guard = imp.send_transfers_id_cache.blocking_lock();
-                    for (pos, obj) in recipients_to_remove {
-                        let actual_pos = pos - items_removed;
-
-                        imp.recipient_model.remove(actual_pos as u32);
-                        let removed_model_item = guard.remove(&obj.endpoint_info().id);
-                        items_removed += 1;
-
-                        tracing::debug!(
-                            endpoint_info = %obj.endpoint_info(),
-                            last_state = ?(
-                                obj.transfer_state(),
-                                &obj.event()
-                                    .as_ref()
-                                    .and_then(|it| it.msg.as_client())
-                                    .as_ref()
-                                    .map(|msg| &msg.state),
-                            ),
-                            model_item_pos = actual_pos,
-                            was_model_item_cached = removed_model_item.is_some(),
-                            "Removed recipient card"
-                        );
-                    }
-                }
+                imp.obj().remove_recipient_cards(|state| match state {
+                    TransferState::Queued
+                    | TransferState::RequestedForConsent
+                    | TransferState::OngoingTransfer => false,
+                    TransferState::AwaitingConsentOrIdle
+                    | TransferState::Failed
+                    | TransferState::Done => true,
+                });
 
                 imp.obj().stop_mdns_discovery();
                 imp.obj().start_mdns_discovery(None);
             }
         ));
+
+        // Unlike refresh, this leaves the discovery running
+        imp.select_recipient_clear_completed_button
+            .connect_clicked(clone!(
+                #[weak]
+                imp,
+                move |_| {
+                    tracing::info!("Clearing completed recipients");
+
+                    imp.obj().remove_recipient_cards(|state| {
+                        matches!(state, TransferState::Failed | TransferState::Done)
+                    });
+                }
+            ));
+    }
+
+    /// Removes the recipient cards whose transfer state matches `filter`, along
+    /// with their cached transfer state.
+    fn remove_recipient_cards(&self, filter: impl Fn(TransferState) -> bool) {
+        let imp = self.imp();
+
+        let mut recipients_to_remove = imp
+            .recipient_model
+            .iter::<SendRequestState>()
+            .enumerate()
+            .filter_map(|(pos, it)| it.ok().and_then(|it| Some((pos, it))))
+            .filter(|(_, it)| filter(it.transfer_state()))
+            .collect::<Vec<_>>();
+        recipients_to_remove.sort_by_key(|(pos, _)| *pos);
+
+        let mut items_removed = 0;
+        let mut guard = imp.send_transfers_id_cache.blocking_lock();
+        for (pos, obj) in recipients_to_remove {
+            let actual_pos = pos - items_removed;
+
+            imp.recipient_model.remove(actual_pos as u32);
+            let removed_model_item = guard.remove(&obj.endpoint_info().id);
+            items_removed += 1;
+
+            tracing::debug!(
+                endpoint_info = %obj.endpoint_info(),
+                last_state = ?(
+                    obj.transfer_state(),
+                    &obj.event()
+                        .as_ref()
+                        .and_then(|it| it.msg.as_client())
+                        .as_ref()
+                        .map(|msg| &msg.state),
+                ),
+                model_item_pos = actual_pos,
+                was_model_item_cached = removed_model_item.is_some(),
+                "Removed recipient card"
+            );
+        }
+    }
+
+    /// Shows what's going on in place of the recipients while there are none,
+    /// there's no point in looking for devices without any network.
+    fn update_recipients_placeholder(&self) {
+        let imp = self.imp();
+
+        let is_empty = imp.recipient_model.n_items() == 0;
+        // Pinned recipients are listed even before anything's been found
+        let is_none_discovered = imp
+            .recipient_model
+            .iter::<SendRequestState>()
+            .filter_map(|it| it.ok())
+            .all(|it| it.endpoint_info().is_pinned_placeholder());
+        let is_offline = imp.network_state.get() == monitors::NetworkState::NoInterface;
+
+        imp.loading_recipients_box
+            .set_visible(is_none_discovered && !is_offline);
+        imp.no_network_status_page
+            .set_visible(is_none_discovered && is_offline);
+        imp.recipients_help_button.set_visible(is_none_discovered);
+        imp.recipient_listbox.set_visible(!is_empty);
     }
 
     fn bottom_bar_status_indicator_ui_update(&self, is_visible: bool) {
         let imp = self.imp();
 
         let network_state = imp.network_state.get();
+        let is_network_available = network_state.is_available();
         let bluetooth_state = imp.bluetooth_state.get();
+        let is_service_running =
+            imp.root_stack.visible_child_name().as_deref() != Some("rqs_error_status_page");
+
+        imp.bottom_bar_retry_button.set_visible(!is_service_running);
+        imp.bottom_bar_visibility_menu_button
+            .set_visible(is_service_running);
+        self.update_visibility_mode_action();
+        // The name is only worth sharing while others can actually find it
+        imp.bottom_bar_copy_name_button.set_visible(
+            is_service_running && is_network_available && bluetooth_state && is_visible,
+        );
 
-        if network_state && bluetooth_state {
+        if !is_service_running {
+            imp.bottom_bar_image
+                .set_icon_name(Some("dialog-error-symbolic"));
+            imp.bottom_bar_title.set_label(&gettext("Service Error"));
+            imp.bottom_bar_image.remove_css_class("accent");
+            imp.bottom_bar_title.remove_css_class("accent");
+            imp.bottom_bar_caption
+                .set_label(&gettext("Nearby devices can't be reached"));
+        } else if is_network_available && bluetooth_state {
             if is_visible {
                 imp.bottom_bar_title.set_label(&gettext("Ready"));
                 imp.bottom_bar_title.add_css_class("accent");
                 imp.bottom_bar_image
                     .set_icon_name(Some("network-available-symbolic"));
                 imp.bottom_bar_image.add_css_class("accent");
-                imp.bottom_bar_caption.set_label(
-                    &formatx!(
+                let caption = match imp.temporary_visibility_deadline.get() {
+                    Some(deadline) => {
+                        let remaining_secs = deadline
+                            .saturating_duration_since(std::time::Instant::now())
+                            .as_secs();
+                        formatx!(
+                            gettext("Visible as {:?} for {}"),
+                            imp.obj().get_device_name_state().as_str(),
+                            format!("{}:{:02}", remaining_secs / 60, remaining_secs % 60)
+                        )
+                    }
+                    None => formatx!(
                         gettext("Visible as {:?}"),
                         imp.obj().get_device_name_state().as_str()
-                    )
-                    .unwrap_or_else(|_| "badly formatted locale string".into()),
-                );
+                    ),
+                };
+                imp.bottom_bar_caption
+                    .set_label(&caption.unwrap_or_else(|_| "badly formatted locale string".into()));
+            } else if imp.settings.boolean("trusted-only") {
+                imp.bottom_bar_title.set_label(&gettext("Trusted Only"));
+                imp.bottom_bar_title.remove_css_class("accent");
+                imp.bottom_bar_image
+                    .set_icon_name(Some("eye-not-looking-symbolic"));
+                imp.bottom_bar_image.remove_css_class("accent");
+                imp.bottom_bar_caption
+                    .set_label(&gettext("Only trusted devices can share with you"));
             } else {
                 imp.bottom_bar_title.set_label(&gettext("Invisible"));
                 imp.bottom_bar_title.remove_css_class("accent");
@@ -1639,6 +4379,21 @@ impl PacketApplicationWindow {
                 imp.bottom_bar_caption
                     .set_label(&gettext("No new devices can share with you"));
             };
+        } else if network_state == monitors::NetworkState::NoInterface {
+            imp.bottom_bar_image
+                .set_icon_name(Some("airplane-mode-symbolic"));
+            imp.bottom_bar_title.set_label(&gettext("Offline"));
+            imp.bottom_bar_image.remove_css_class("accent");
+            imp.bottom_bar_title.remove_css_class("accent");
+
+            if bluetooth_state {
+                imp.bottom_bar_caption
+                    .set_label(&gettext("Turn off airplane mode or connect to Wi-Fi"));
+            } else {
+                imp.bottom_bar_caption.set_label(&gettext(
+                    "Turn off airplane mode, then connect to Wi-Fi and turn on Bluetooth",
+                ));
+            }
         } else {
             imp.bottom_bar_image
                 .set_icon_name(Some("horizontal-arrows-long-x-symbolic"));
@@ -1646,13 +4401,13 @@ impl PacketApplicationWindow {
             imp.bottom_bar_image.remove_css_class("accent");
             imp.bottom_bar_title.remove_css_class("accent");
 
-            if !network_state && !bluetooth_state {
+            if !is_network_available && !bluetooth_state {
                 imp.bottom_bar_caption
                     .set_label(&gettext("Connect to Wi-Fi and turn on Bluetooth"));
-            } else if !network_state && bluetooth_state {
+            } else if !is_network_available && bluetooth_state {
                 imp.bottom_bar_caption
                     .set_label(&gettext("Connect to Wi-Fi"));
-            } else if network_state && !bluetooth_state {
+            } else if is_network_available && !bluetooth_state {
                 imp.bottom_bar_caption
                     .set_label(&gettext("Turn on Bluetooth"));
             }
@@ -1673,13 +4428,16 @@ impl PacketApplicationWindow {
                             imp.bottom_bar_status.set_halign(gtk::Align::Start);
                             imp.bottom_bar_status_top.set_halign(gtk::Align::Start);
                             imp.bottom_bar_caption.set_xalign(0.);
+                            imp.bottom_bar_caption_box.set_halign(gtk::Align::Start);
                             imp.bottom_bar_spacer.set_visible(true);
-                            imp.manage_files_send_button.set_visible(true);
+                            imp.manage_files_send_button
+                                .set_visible(!imp.settings.boolean("receive-only-mode"));
                         }
                         _ => {
                             imp.bottom_bar_status.set_halign(gtk::Align::Center);
                             imp.bottom_bar_status_top.set_halign(gtk::Align::Center);
                             imp.bottom_bar_caption.set_xalign(0.5);
+                            imp.bottom_bar_caption_box.set_halign(gtk::Align::Center);
                             imp.bottom_bar_spacer.set_visible(false);
                             imp.manage_files_send_button.set_visible(false);
                         }
@@ -1688,11 +4446,57 @@ impl PacketApplicationWindow {
             }
         ));
 
+        // Reflect the RQS service health, it's stuck on the error page if
+        // setting it up failed until it's restarted successfully
+        imp.root_stack.connect_visible_child_name_notify(clone!(
+            #[weak]
+            imp,
+            move |_| {
+                imp.obj().bottom_bar_status_indicator_ui_update(
+                    imp.device_visibility_switch.is_active(),
+                );
+            }
+        ));
+        imp.bottom_bar_retry_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.restart_rqs_service();
+            }
+        ));
+        imp.bottom_bar_copy_name_button.connect_clicked(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| {
+                this.clipboard()
+                    .set_text(this.get_device_name_state().as_str());
+                this.add_toast(&gettext("Copied device name to clipboard"));
+            }
+        ));
+        // Only shown while receiving with the toast receive UI
+        imp.bottom_bar_receive_cancel_button.connect_clicked(clone!(
+            #[weak]
+            imp,
+            move |_| {
+                let receive_state = imp
+                    .receive_transfer_cache
+                    .blocking_lock()
+                    .as_ref()
+                    .map(|it| it.state.clone());
+                if let Some(receive_state) = receive_state {
+                    receive_state.set_user_action(Some(UserAction::TransferCancel));
+                }
+            }
+        ));
+
         self.bottom_bar_status_indicator_ui_update(imp.device_visibility_switch.is_active());
-        imp.device_visibility_switch.connect_active_notify(clone!(
+        let signal_handle = imp.device_visibility_switch.connect_active_notify(clone!(
             #[weak]
             imp,
             move |obj| {
+                // A manual toggle takes over from the temporary visibility
+                imp.obj().stop_temporary_visibility();
+
                 imp.obj()
                     .bottom_bar_status_indicator_ui_update(obj.is_active());
 
@@ -1703,15 +4507,157 @@ impl PacketApplicationWindow {
                 };
 
                 glib::spawn_future_local(async move {
-                    imp.rqs
-                        .lock()
-                        .await
-                        .as_mut()
-                        .unwrap()
-                        .change_visibility(visibility);
+                    // Applied from the setting once the service is up again
+                    if let Some(rqs) = imp.rqs.lock().await.as_mut() {
+                        rqs.change_visibility(visibility);
+                    }
                 });
             }
         ));
+        imp.device_visibility_switch_handler_id
+            .replace(Some(signal_handle));
+
+        // Not blocked along with the handler above, so that it also follows
+        // the visibility changes made by the app itself
+        self.update_receive_temporarily_action();
+        imp.device_visibility_switch.connect_active_notify(clone!(
+            #[weak(rename_to = this)]
+            self,
+            move |_| this.update_receive_temporarily_action()
+        ));
+    }
+
+    /// Becoming visible temporarily only makes sense while invisible, or to
+    /// extend the temporary visibility, since it goes invisible once it's up.
+    fn update_receive_temporarily_action(&self) {
+        let imp = self.imp();
+
+        let is_enabled = !imp.device_visibility_switch.is_active()
+            || imp.temporary_visibility_token.borrow().is_some();
+        if let Some(action) = self
+            .lookup_action("receive-temporarily")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_enabled(is_enabled);
+        }
+    }
+
+    /// Becomes visible for [`TEMPORARY_VISIBILITY_DURATION`], reverting back to
+    /// invisible afterwards.
+    fn start_temporary_visibility(&self) {
+        let imp = self.imp();
+
+        if imp.device_visibility_switch.is_active()
+            && imp.temporary_visibility_token.borrow().is_none()
+        {
+            tracing::debug!("Already visible, not becoming visible temporarily");
+            return;
+        }
+
+        self.stop_temporary_visibility();
+
+        tracing::info!(duration = ?TEMPORARY_VISIBILITY_DURATION, "Temporarily becoming visible");
+
+        let token = CancellationToken::new();
+        let deadline = std::time::Instant::now() + TEMPORARY_VISIBILITY_DURATION;
+        imp.temporary_visibility_token.replace(Some(token.clone()));
+        imp.temporary_visibility_deadline.set(Some(deadline));
+
+        self.set_device_visibility(true);
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                // Tick every second for the countdown
+                while std::time::Instant::now() < deadline {
+                    tokio::select! {
+                        _ = token.cancelled() => {
+                            return;
+                        }
+                        _ = futures_timer::Delay::new(std::time::Duration::from_secs(1)) => {
+                            this.bottom_bar_status_indicator_ui_update(
+                                this.imp().device_visibility_switch.is_active(),
+                            );
+                        }
+                    }
+                }
+
+                tracing::info!("Temporary visibility elapsed");
+                this.stop_temporary_visibility();
+                this.set_device_visibility(false);
+            }
+        ));
+    }
+
+    fn stop_temporary_visibility(&self) {
+        let imp = self.imp();
+
+        if let Some(token) = imp.temporary_visibility_token.take() {
+            tracing::info!("Stopping temporary visibility");
+            token.cancel();
+        }
+        imp.temporary_visibility_deadline.set(None);
+    }
+
+    /// Changes the visibility without going through the visibility switch's
+    /// handler, which would otherwise stop the temporary visibility.
+    fn set_device_visibility(&self, is_visible: bool) {
+        let imp = self.imp();
+
+        with_signals_blocked(
+            &[(
+                &imp.device_visibility_switch.get(),
+                imp.device_visibility_switch_handler_id.borrow().as_ref(),
+            )],
+            || {
+                imp.device_visibility_switch.set_active(is_visible);
+            },
+        );
+        self.bottom_bar_status_indicator_ui_update(is_visible);
+
+        let visibility = if is_visible {
+            rqs_lib::Visibility::Visible
+        } else {
+            rqs_lib::Visibility::Invisible
+        };
+        glib::spawn_future_local(clone!(
+            #[weak]
+            imp,
+            async move {
+                if let Some(rqs) = imp.rqs.lock().await.as_mut() {
+                    rqs.change_visibility(visibility);
+                }
+            }
+        ));
+    }
+
+    /// Reflects a visibility change that didn't originate from the visibility
+    /// switch (e.g. rqs_lib toggling it on its own) back into the UI.
+    ///
+    /// The switch is bound to the `device-visibility` key, so the effective
+    /// state also gets persisted.
+    fn sync_device_visibility_ui(&self, is_visible: bool) {
+        let imp = self.imp();
+
+        // Being undiscoverable while idle doesn't change the preference
+        if imp.device_visibility_switch.is_active() == is_visible || imp.is_idle_paused.get() {
+            return;
+        }
+
+        tracing::info!(is_visible, "Syncing device visibility from RQS");
+
+        with_signals_blocked(
+            &[(
+                &imp.device_visibility_switch.get(),
+                imp.device_visibility_switch_handler_id.borrow().as_ref(),
+            )],
+            || {
+                imp.device_visibility_switch.set_active(is_visible);
+            },
+        );
+
+        self.bottom_bar_status_indicator_ui_update(is_visible);
     }
 
     fn handle_added_files_to_send(&self, model: &gio::ListStore, files: Vec<gio::File>) -> bool {
@@ -1719,15 +4665,37 @@ impl PacketApplicationWindow {
 
         tracing::debug!(selected_files = ?files.iter().map(|it| it.path()).collect::<Vec<_>>());
 
-        let (files, is_already_in_model) = Self::filter_added_files(model, files);
+        let (files, is_already_in_model, empty_files) =
+            Self::filter_added_files(model, files, imp.settings.boolean("allow-empty-files"));
+
+        if !empty_files.is_empty() {
+            let file_names = empty_files
+                .iter()
+                .filter_map(|it| it.basename())
+                .map(|it| it.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.add_toast(
+                &formatx!(
+                    ngettext(
+                        "Skipped empty file: {}",
+                        "Skipped empty files: {}",
+                        empty_files.len() as u32
+                    ),
+                    file_names
+                )
+                .unwrap_or_else(|_| "badly formatted locale string".into()),
+            );
+        }
+
         if is_already_in_model {
             return true;
         }
 
-        // TODO: Maybe don't show this if the only filtered out files
-        // are the 0 byte sized
         if files.len() == 0 {
-            self.add_toast(&gettext("Couldn't open files"));
+            if empty_files.is_empty() {
+                self.add_toast(&gettext("Couldn't open files"));
+            }
 
             false
         } else {
@@ -1748,6 +4716,7 @@ impl PacketApplicationWindow {
             for file in &files {
                 model.append(file);
             }
+            self.confirm_large_selection(model, files);
 
             let Some(tag) = imp.main_nav_view.visible_page_tag() else {
                 return false;
@@ -1761,6 +4730,114 @@ impl PacketApplicationWindow {
         }
     }
 
+    /// Asks whether to keep the just added files if the selection has grown past
+    /// the thresholds, as dropping a huge folder by accident would otherwise
+    /// queue all of it up. Sizes are added up off the main thread since that
+    /// can take a while for large selections.
+    fn confirm_large_selection(&self, model: &gio::ListStore, added_files: Vec<gio::File>) {
+        let imp = self.imp();
+
+        let max_file_count = imp.settings.int("max-files-warn-threshold") as usize;
+        let max_total_size = imp.settings.int("max-size-warn-threshold") as u64 * 1024 * 1024;
+        if max_file_count == 0 && max_total_size == 0 {
+            return;
+        }
+
+        let file_paths = model
+            .iter::<gio::File>()
+            .filter_map(|it| it.ok())
+            .filter_map(|it| it.path())
+            .collect::<Vec<_>>();
+        let file_count = file_paths.len();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            #[weak]
+            model,
+            async move {
+                let total_size = if max_total_size == 0 {
+                    0
+                } else {
+                    tokio_runtime()
+                        .spawn_blocking(move || {
+                            file_paths
+                                .iter()
+                                .filter_map(|it| fs_err::metadata(it).ok())
+                                .map(|it| it.len())
+                                .sum::<u64>()
+                        })
+                        .await
+                        .inspect_err(|err| tracing::warn!(%err))
+                        .unwrap_or_default()
+                };
+
+                let is_too_many = max_file_count != 0 && file_count > max_file_count;
+                let is_too_large = max_total_size != 0 && total_size > max_total_size;
+                if !is_too_many && !is_too_large {
+                    return;
+                }
+                tracing::info!(
+                    file_count,
+                    total_size,
+                    "Selected files are past the warning thresholds"
+                );
+
+                // The size isn't looked up without a size threshold
+                let body = if max_total_size == 0 {
+                    formatx!(
+                        ngettext(
+                            "{} file is selected.",
+                            "{} files are selected.",
+                            file_count as u32
+                        ),
+                        file_count
+                    )
+                } else {
+                    formatx!(
+                        ngettext(
+                            "{} file adding up to {} is selected.",
+                            "{} files adding up to {} are selected.",
+                            file_count as u32
+                        ),
+                        file_count,
+                        utils::format_size_localized(total_size)
+                    )
+                }
+                .unwrap_or_else(|_| "badly formatted locale string".into());
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(&gettext("Send All of These Files?"))
+                    .body(&body)
+                    .default_response("keep")
+                    .close_response("keep")
+                    .build();
+                dialog.add_responses(&[
+                    ("remove", &gettext("Remove Added Files")),
+                    ("keep", &gettext("Keep")),
+                ]);
+                dialog.set_response_appearance("remove", adw::ResponseAppearance::Destructive);
+                dialog.connect_response(
+                    Some("remove"),
+                    clone!(
+                        #[weak]
+                        this,
+                        #[weak]
+                        model,
+                        move |_, _| {
+                            tracing::info!(count = added_files.len(), "Removing the added files");
+                            for file in &added_files {
+                                widgets::remove_file(&this, &model, file);
+                            }
+                        }
+                    ),
+                );
+
+                dialog.present(this.root().as_ref());
+            }
+        ));
+    }
+
     fn add_files_via_dialog(&self) {
         let imp = self.imp();
         gtk::FileDialog::new().open_multiple(
@@ -1787,10 +4864,17 @@ impl PacketApplicationWindow {
         );
     }
 
-    fn filter_added_files(model: &gio::ListStore, files: Vec<gio::File>) -> (Vec<gio::File>, bool) {
+    /// Returns the files to add, whether all of them were already added, and the
+    /// empty files that got filtered out.
+    fn filter_added_files(
+        model: &gio::ListStore,
+        files: Vec<gio::File>,
+        allow_empty_files: bool,
+    ) -> (Vec<gio::File>, bool, Vec<gio::File>) {
         let files_len = files.len();
 
         let mut already_included_count = 0usize;
+        let mut empty_files = vec![];
         let filtered_files = files
             .into_iter()
             .filter(|file| {
@@ -1800,8 +4884,11 @@ impl PacketApplicationWindow {
                 ) == gio::FileType::Regular
             })
             .filter(|it| {
-                // Don't send 0 byte files
+                // Don't send 0 byte files by default
                 // Because the rqs_lib expect files
+                if allow_empty_files {
+                    return true;
+                }
 
                 let file_size = it
                     .query_info(
@@ -1812,7 +4899,12 @@ impl PacketApplicationWindow {
                     .map(|it| it.size())
                     .unwrap_or_default();
 
-                file_size != 0
+                if file_size == 0 {
+                    empty_files.push(it.clone());
+                    return false;
+                }
+
+                true
             })
             .filter(|file| {
                 for existing_file in model.iter::<gio::File>().filter_map(|it| it.ok()) {
@@ -1827,10 +4919,10 @@ impl PacketApplicationWindow {
             .collect::<Vec<_>>();
 
         let is_already_in_model = already_included_count == files_len;
-        (filtered_files, is_already_in_model)
+        (filtered_files, is_already_in_model, empty_files)
     }
 
-    fn start_mdns_discovery(&self, force: Option<bool>) {
+    pub fn start_mdns_discovery(&self, force: Option<bool>) {
         let imp = self.imp();
 
         if (force.is_some() && force.unwrap_or_default())
@@ -1844,11 +4936,13 @@ impl PacketApplicationWindow {
                 #[weak(rename_to = rqs)]
                 imp.rqs,
                 async move {
+                    let mut rqs_guard = rqs.lock().await;
+                    let Some(rqs) = rqs_guard.as_mut() else {
+                        tracing::warn!("Can't start mDNS discovery, the service isn't running");
+                        return;
+                    };
+
                     _ = rqs
-                        .lock()
-                        .await
-                        .as_mut()
-                        .unwrap()
                         .discovery(
                             mdns_discovery_broadcast_tx
                                 .lock()
@@ -1878,7 +4972,10 @@ impl PacketApplicationWindow {
                 #[weak(rename_to = rqs)]
                 imp.rqs,
                 async move {
-                    rqs.lock().await.as_mut().unwrap().stop_discovery();
+                    // Nothing to stop otherwise
+                    if let Some(rqs) = rqs.lock().await.as_mut() {
+                        rqs.stop_discovery();
+                    }
                 }
             ));
 
@@ -1886,6 +4983,112 @@ impl PacketApplicationWindow {
         }
     }
 
+    /// Keeps discovery running outside of the recipients dialog with
+    /// `eager-discovery`, only for as long as the window is shown and not
+    /// paused for being idle.
+    fn update_eager_discovery(&self) {
+        let imp = self.imp();
+
+        // Discovery is the dialog's to manage while it's open
+        if imp.is_recipients_dialog_opened.get() || imp.is_idle_paused.get() {
+            return;
+        }
+
+        let is_service_running = imp.rqs.try_lock().is_ok_and(|it| it.is_some());
+        if imp.settings.boolean("eager-discovery") && self.is_visible() && is_service_running {
+            self.start_mdns_discovery(None);
+        } else {
+            self.stop_mdns_discovery();
+        }
+    }
+
+    /// Pauses discovery and becomes undiscoverable once the window has been
+    /// inactive for `discovery-idle-timeout` minutes, to save power. The RQS
+    /// service itself keeps running.
+    fn schedule_idle_pause(&self) {
+        let imp = self.imp();
+
+        self.cancel_idle_pause();
+
+        let timeout_mins = imp.settings.int("discovery-idle-timeout").max(0) as u64;
+        if timeout_mins == 0 {
+            return;
+        }
+
+        let token = CancellationToken::new();
+        imp.idle_pause_token.replace(Some(token.clone()));
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                tokio::select! {
+                    _ = futures_timer::Delay::new(std::time::Duration::from_secs(timeout_mins * 60)) => {
+                        this.imp().idle_pause_token.take();
+                        this.pause_for_idle();
+                    }
+                    _ = token.cancelled() => {}
+                }
+            }
+        ));
+    }
+
+    fn cancel_idle_pause(&self) {
+        if let Some(token) = self.imp().idle_pause_token.take() {
+            token.cancel();
+        }
+    }
+
+    fn pause_for_idle(&self) {
+        let imp = self.imp();
+
+        // Receiving for a while was asked for explicitly
+        if imp.temporary_visibility_deadline.get().is_some() || imp.is_idle_paused.get() {
+            return;
+        }
+
+        tracing::info!("Pausing discovery while idle");
+        imp.is_idle_paused.set(true);
+
+        imp.was_mdns_discovery_on_before_idle
+            .set(imp.is_mdns_discovery_on.get());
+        self.stop_mdns_discovery();
+
+        if imp.device_visibility_switch.is_active() {
+            self.change_rqs_visibility(rqs_lib::Visibility::Invisible);
+        }
+    }
+
+    fn resume_from_idle(&self) {
+        let imp = self.imp();
+
+        self.cancel_idle_pause();
+        if !imp.is_idle_paused.replace(false) {
+            return;
+        }
+
+        tracing::info!("Resuming discovery");
+
+        if imp.was_mdns_discovery_on_before_idle.take() {
+            self.start_mdns_discovery(None);
+        }
+        if imp.device_visibility_switch.is_active() {
+            self.change_rqs_visibility(rqs_lib::Visibility::Visible);
+        }
+    }
+
+    /// Changes the advertised visibility without touching the preference.
+    fn change_rqs_visibility(&self, visibility: rqs_lib::Visibility) {
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = this)]
+            self,
+            async move {
+                if let Some(rqs) = this.imp().rqs.lock().await.as_mut() {
+                    rqs.change_visibility(visibility);
+                }
+            }
+        ));
+    }
+
     fn is_no_file_being_send(&self) -> bool {
         let imp = self.imp();
 
@@ -1923,11 +5126,22 @@ impl PacketApplicationWindow {
             #[weak(rename_to = this)]
             self,
             async move {
-                this.imp()
-                    .root_stack
-                    .set_visible_child_name("loading_service_page");
-                _ = this.stop_rqs_service().await;
-                _ = this.setup_rqs_service().await;
+                let service_ops_lock = this.imp().service_ops_lock.clone();
+                restart_service_serialized(
+                    &service_ops_lock,
+                    async || {
+                        tracing::debug!("Restarting RQS service");
+
+                        this.imp()
+                            .root_stack
+                            .set_visible_child_name("loading_service_page");
+                        _ = this.stop_rqs_service().await;
+                    },
+                    async || {
+                        _ = this.setup_rqs_service().await;
+                    },
+                )
+                .await;
             }
         ))
     }
@@ -1967,12 +5181,34 @@ impl PacketApplicationWindow {
     fn setup_connection_monitors(&self) {
         let imp = self.imp();
 
-        let (tx, mut network_rx) = watch::channel(false);
+        self.connect_visible_notify(|win| {
+            win.update_eager_discovery();
+        });
+
+        self.connect_is_active_notify(|win| {
+            if win.is_active() {
+                win.refresh_bluetooth_state();
+                win.resume_from_idle();
+            } else {
+                win.schedule_idle_pause();
+            }
+        });
+
+        let (tx, mut network_rx) = watch::channel(monitors::NetworkState::default());
+        let tx = Rc::new(tx);
         // Set initial state
-        _ = tx.send(imp.network_monitor.is_network_available());
+        _ = tx.send(monitors::NetworkState::from_monitor(&imp.network_monitor));
+        // Connectivity changes don't always come with `::network-changed`
+        imp.network_monitor.connect_connectivity_notify(clone!(
+            #[strong]
+            tx,
+            move |monitor| {
+                _ = tx.send(monitors::NetworkState::from_monitor(monitor));
+            }
+        ));
         imp.network_monitor
             .connect_network_changed(move |monitor, _| {
-                _ = tx.send(monitor.is_network_available());
+                _ = tx.send(monitors::NetworkState::from_monitor(monitor));
             });
 
         glib::spawn_future_local(clone!(
@@ -1997,6 +5233,7 @@ impl PacketApplicationWindow {
                     })
                     .unwrap_or_default();
                 let (tx, mut bluetooth_rx) = watch::channel(bluetooth_initial_state);
+                this.imp().bluetooth_state_sender.replace(Some(tx.clone()));
                 glib::spawn_future(async move {
                     if let Err(err) = monitors::spawn_bluetooth_power_monitor_task(conn, tx)
                         .await
@@ -2050,14 +5287,19 @@ impl PacketApplicationWindow {
                             if is_state_changed.is_some() {
                                 if let Some(ChangedState::Network) = is_state_changed {
                                     tracing::info!(
-                                        network_state = imp.network_state.get(),
+                                        network_state = ?imp.network_state.get(),
                                         "Network state changed"
                                     );
+
+                                    this.update_recipients_placeholder();
                                 }
 
                                 this.bottom_bar_status_indicator_ui_update(
                                     imp.device_visibility_switch.is_active(),
                                 );
+
+                                // Errors only when nobody's subscribed
+                                _ = imp.connectivity_sender.send(this.connectivity_state());
                             }
                         }
                     }
@@ -2066,6 +5308,77 @@ impl PacketApplicationWindow {
         ));
     }
 
+    pub fn connectivity_state(&self) -> monitors::ConnectivityState {
+        let imp = self.imp();
+
+        monitors::ConnectivityState {
+            network: imp.network_state.get(),
+            bluetooth: imp.bluetooth_state.get(),
+        }
+    }
+
+    /// A transfer time estimator for `total_len` bytes, smoothed as per the
+    /// `steady-time-estimates` preference.
+    pub fn new_eta_estimator(&self, total_len: usize) -> utils::DataTransferEta {
+        let mut eta = if self.imp().settings.boolean("steady-time-estimates") {
+            // The usual alpha for an EMA spanning N samples
+            let alpha = 2. / (STEADY_ETA_WINDOW_SECS as f64 + 1.);
+            utils::DataTransferEta::with_window(STEADY_ETA_WINDOW_SECS)
+                .smoothing(utils::EtaSmoothing::ExponentialMovingAverage { alpha })
+        } else {
+            utils::DataTransferEta::default()
+        };
+        eta.prepare_for_new_transfer(Some(total_len));
+        eta
+    }
+
+    /// Asks rqs_lib to cancel the transfer with `id`, whichever way it's going.
+    /// Does nothing if the service isn't running.
+    pub async fn send_transfer_cancel(&self, id: String) {
+        if let Some(rqs) = self.imp().rqs.lock().await.as_mut() {
+            _ = rqs
+                .message_sender
+                .send(rqs_lib::channel::ChannelMessage {
+                    id,
+                    msg: rqs_lib::channel::Message::Lib {
+                        action: rqs_lib::channel::TransferAction::TransferCancel,
+                    },
+                })
+                .inspect_err(|err| tracing::error!(%err));
+        }
+    }
+
+    /// Subscribes to the changes in connectivity, the current state can be
+    /// had from [`Self::connectivity_state`].
+    pub fn subscribe_connectivity(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<monitors::ConnectivityState> {
+        self.imp().connectivity_sender.subscribe()
+    }
+
+    /// Re-queries the Bluetooth powered state, in case the monitor missed
+    /// a change while it was reconnecting.
+    fn refresh_bluetooth_state(&self) {
+        let imp = self.imp();
+
+        let Some(conn) = imp.dbus_system_conn.borrow().clone() else {
+            return;
+        };
+        let Some(sender) = imp.bluetooth_state_sender.borrow().clone() else {
+            return;
+        };
+
+        glib::spawn_future_local(async move {
+            match monitors::is_bluetooth_powered(&conn).await {
+                Ok(powered) => monitors::send_bluetooth_state(&sender, powered),
+                Err(err) => tracing::warn!(
+                    "{:#}",
+                    anyhow!(err).context("Failed to refresh the Bluetooth powered state")
+                ),
+            }
+        });
+    }
+
     fn setup_notification_actions_monitor(&self) {
         let imp = self.imp();
 
@@ -2081,6 +5394,11 @@ impl PacketApplicationWindow {
                         let action = action_stream.next().await.context("Stream exhausted")?;
                         tracing::info!(action_name = ?action.name(), id = action.id(), params = ?action.parameter(), "Notification action received");
 
+                        if action.name() == "dismiss" {
+                            utils::remove_notification(action.id().to_string());
+                            continue;
+                        }
+
                         if let Some(cached_transfer) = imp.receive_transfer_cache.lock().await.as_mut() {
                             match action.name() {
                                 "consent-accept" => {
@@ -2096,10 +5414,19 @@ impl PacketApplicationWindow {
                                 "consent-decline" => {
                                     cached_transfer.state.set_user_action(Some(UserAction::ConsentDecline));
                                 },
+                                "consent-decline-invisible" => {
+                                    cached_transfer.state.set_user_action(Some(UserAction::ConsentDecline));
+
+                                    // Keeps the sender from simply trying again
+                                    tracing::info!("Going invisible from the consent notification");
+                                    let obj = imp.obj();
+                                    obj.stop_temporary_visibility();
+                                    obj.set_device_visibility(false);
+                                },
                                 "transfer-cancel" => {
                                     cached_transfer.state.set_user_action(Some(UserAction::TransferCancel));
                                 },
-                                "open-folder" => {
+                                "open-folder" | "open-file" => {
                                     if let Some(param) = action.parameter().get(0).and_then(|it| {
                                         it.downcast_ref::<String>()
                                             .inspect_err(|err| tracing::warn!("{err:#}"))
@@ -2112,7 +5439,7 @@ impl PacketApplicationWindow {
                                         );
                                     }
                                 },
-                                "copy-text" => {
+                                "copy-text" | "copy-path" => {
                                     if let Some(param) = action.parameter().get(0).and_then(|it| {
                                         it.downcast_ref::<String>()
                                             .inspect_err(|err| tracing::warn!("{err:#}"))
@@ -2148,37 +5475,71 @@ impl PacketApplicationWindow {
             .settings
             .boolean("enable-static-port")
             .then(|| imp.settings.int("static-port-number") as u32);
+        let interface_name = imp.settings.string("interface-name");
+        if !interface_name.is_empty() {
+            tracing::warn!(
+                ?interface_name,
+                "Network interface selection isn't supported by rqs_lib yet, using all interfaces"
+            );
+        }
         let rqs_init_handle = glib::spawn_future_local(clone!(
             #[weak]
             imp,
             async move {
                 let _imp = imp.clone();
                 if let Err(err) = async move || -> anyhow::Result<()> {
-                    let (rqs, run_result) = tokio_runtime()
-                        .spawn(async move {
-                            tracing::info!(
-                                ?device_name,
-                                visibility = ?is_device_visible,
-                                ?download_path,
-                                ?static_port,
-                                "Starting RQS service"
-                            );
+                    // Retrying here is to get past transient failures, such as
+                    // the network not being up yet right after login. Each call
+                    // starts over with a fresh backoff, so retrying manually from
+                    // the error page gets the full number of attempts again.
+                    let (rqs, file_sender, ble_receiver) = retry_with_backoff(
+                        RQS_SETUP_MAX_ATTEMPTS,
+                        RQS_SETUP_INITIAL_RETRY_DELAY,
+                        || {
+                            let device_name = device_name.clone();
+                            let download_path = download_path.clone();
+                            async move {
+                                tokio_runtime()
+                                    .spawn(async move {
+                                        tracing::info!(
+                                            ?device_name,
+                                            visibility = ?is_device_visible,
+                                            ?download_path,
+                                            ?static_port,
+                                            "Starting RQS service"
+                                        );
 
-                            let mut rqs = rqs_lib::RQS::new(
-                                if is_device_visible {
-                                    rqs_lib::Visibility::Visible
-                                } else {
-                                    rqs_lib::Visibility::Invisible
-                                },
-                                static_port,
-                                Some(download_path),
-                                Some(device_name.to_string()),
-                            );
+                                        // TODO: Let the user pick the advertised device type
+                                        // (Laptop/Desktop/Tablet/Phone) once rqs_lib takes it as
+                                        // a parameter, it always advertises a Laptop for now.
+                                        let mut rqs = rqs_lib::RQS::new(
+                                            if is_device_visible {
+                                                rqs_lib::Visibility::Visible
+                                            } else {
+                                                rqs_lib::Visibility::Invisible
+                                            },
+                                            static_port,
+                                            Some(download_path),
+                                            Some(device_name.to_string()),
+                                        );
 
-                            let run_result = rqs.run().await;
-                            (rqs, run_result)
-                        })
-                        .await?;
+                                        match rqs.run().await {
+                                            Ok((file_sender, ble_receiver)) => {
+                                                Ok((rqs, file_sender, ble_receiver))
+                                            }
+                                            Err(err) => {
+                                                // Clean up whatever got started before
+                                                // the next attempt
+                                                rqs.stop().await;
+                                                Err(anyhow::anyhow!(err))
+                                            }
+                                        }
+                                    })
+                                    .await?
+                            }
+                        },
+                    )
+                    .await?;
 
                     *imp.rqs.lock().await = Some(rqs);
                     let (mdns_discovery_broadcast_tx, _) =
@@ -2186,21 +5547,36 @@ impl PacketApplicationWindow {
                     *imp.mdns_discovery_broadcast_tx.lock().await =
                         Some(mdns_discovery_broadcast_tx);
 
-                    let (file_sender, ble_receiver) = run_result?;
                     *imp.file_sender.lock().await = Some(file_sender);
                     *imp.ble_receiver.lock().await = Some(ble_receiver);
 
                     imp.root_stack.get().set_visible_child_name("main_page");
 
                     spawn_rqs_receiver_tasks(&imp);
+                    imp.obj().update_eager_discovery();
 
                     Ok(())
                 }()
                 .await
                 {
+                    let is_port_in_use = _imp.settings.boolean("enable-static-port")
+                        && is_address_in_use_error(&err);
                     let err = err.context("Failed to setup Packet");
-                    tracing::error!("{err:#}");
+                    tracing::error!(is_port_in_use, "{err:#}");
 
+                    _imp.rqs_error_status.set_description(Some(&if is_port_in_use {
+                        formatx!(
+                            gettext(
+                                "The static port {} is already in use by another app. Free it up, or switch to a dynamic port."
+                            ),
+                            _imp.settings.int("static-port-number")
+                        )
+                        .unwrap_or_else(|_| "badly formatted locale string".into())
+                    } else {
+                        gettext("An error occurred while trying to setup Packet")
+                    }));
+                    _imp.rqs_error_dynamic_port_button
+                        .set_visible(is_port_in_use);
                     _imp.root_stack
                         .get()
                         .set_visible_child_name("rqs_error_status_page");
@@ -2270,6 +5646,47 @@ impl PacketApplicationWindow {
                             TransferState::SentPairedKeyResult => {}
                             TransferState::ReceivedPairedKeyResult => {}
                             TransferState::WaitingForUserConsent => {
+                                // rqs_lib lets requests in while another is underway,
+                                // but there's only room for one at a time. Declining
+                                // right away gives the sender an answer, rather than
+                                // leaving them waiting on a request nobody sees.
+                                let is_busy = imp
+                                    .receive_transfer_cache
+                                    .lock()
+                                    .await
+                                    .as_ref()
+                                    .is_some_and(|it| it.transfer_id != *id && !it.is_settled());
+                                if is_busy {
+                                    let message = objects::ChannelMessage(channel_message.clone());
+                                    let device_name = message.device_name();
+                                    tracing::info!(
+                                        %id,
+                                        %device_name,
+                                        "Declining request while another transfer is underway"
+                                    );
+
+                                    if let Some(rqs) = imp.rqs.lock().await.as_mut() {
+                                        _ = rqs
+                                            .message_sender
+                                            .send(rqs_lib::channel::ChannelMessage {
+                                                id: id.clone(),
+                                                msg: rqs_lib::channel::Message::Lib {
+                                                    action: rqs_lib::channel::TransferAction::ConsentDecline,
+                                                },
+                                            })
+                                            .inspect_err(|err| tracing::error!(%err));
+                                    }
+
+                                    imp.obj().add_toast(
+                                        &formatx!(
+                                            gettext("Declined {}, another transfer is in progress"),
+                                            message.display_device_name()
+                                        )
+                                        .unwrap_or_else(|_| "badly formatted locale string".into()),
+                                    );
+                                    continue;
+                                }
+
                                 // Receive data transfer requests
                                 {
                                     let channel_message = objects::ChannelMessage(channel_message);
@@ -2306,8 +5723,11 @@ impl PacketApplicationWindow {
                                 match client_msg.kind {
                                     rqs_lib::channel::TransferKind::Inbound => {
                                         // Receive
+                                        // Events of a request declined for being busy
+                                        // aren't for the one in the cache
                                         if let Some(cached_transfer) =
                                             imp.receive_transfer_cache.lock().await.as_mut()
+                                            && cached_transfer.transfer_id == *id
                                         {
                                             if !cached_transfer.auto_decline_ctk.is_cancelled() {
                                                 // Cancel auto-decline
@@ -2392,11 +5812,35 @@ impl PacketApplicationWindow {
                                 // Update endpoint
                                 let endpoint_info = objects::EndpointInfo(endpoint_info);
                                 tracing::info!(%endpoint_info, "Updated endpoint");
+                                let prev_endpoint_info = data_transfer.endpoint_info();
+                                let is_sort_key_changed = prev_endpoint_info.present.is_some()
+                                    != endpoint_info.present.is_some()
+                                    || prev_endpoint_info.name != endpoint_info.name;
                                 data_transfer.set_endpoint_info(endpoint_info);
+
+                                if is_sort_key_changed
+                                    && let Some(sorter) = imp.recipient_sorted_model.sorter()
+                                {
+                                    sorter.changed(gtk::SorterChange::Different);
+                                }
                             } else {
                                 // Set new endpoint
                                 let endpoint_info = objects::EndpointInfo(endpoint_info);
                                 tracing::info!(%endpoint_info, "Discovered endpoint");
+
+                                // The pinned recipient's stand-in makes way for it
+                                if let Some(pos) = imp
+                                    .recipient_model
+                                    .iter::<SendRequestState>()
+                                    .filter_map(|it| it.ok())
+                                    .position(|it| {
+                                        let it = it.endpoint_info();
+                                        it.is_pinned_placeholder() && it.name == endpoint_info.name
+                                    })
+                                {
+                                    imp.recipient_model.remove(pos as u32);
+                                }
+
                                 let obj = SendRequestState::new();
                                 let id = endpoint_info.id.clone();
                                 obj.set_endpoint_info(endpoint_info);
@@ -2411,6 +5855,7 @@ impl PacketApplicationWindow {
                 .borrow_mut()
                 .push(LoopingTaskHandle::Glib(handle));
 
+            let (tx, rx) = async_channel::bounded(1);
             let handle = tokio_runtime().spawn(clone!(
                 #[weak(rename_to = rqs)]
                 imp.rqs,
@@ -2428,10 +5873,18 @@ impl PacketApplicationWindow {
                     loop {
                         match visibility_receiver.changed().await {
                             Ok(_) => {
-                                // FIXME: Update visibility in UI, not used for now
-                                // since visibility is not being set from outside
-                                let visibility = visibility_receiver.borrow_and_update();
-                                tracing::debug!(?visibility, "Visibility change");
+                                let is_visible = {
+                                    let visibility = visibility_receiver.borrow_and_update();
+                                    tracing::debug!(?visibility, "Visibility change");
+
+                                    !matches!(*visibility, rqs_lib::Visibility::Invisible)
+                                };
+
+                                // The UI side is gone, e.g. aborted while
+                                // restarting the service
+                                if tx.send(is_visible).await.is_err() {
+                                    break;
+                                }
                             }
                             Err(err) => {
                                 tracing::error!(
@@ -2447,6 +5900,19 @@ impl PacketApplicationWindow {
                 .borrow_mut()
                 .push(LoopingTaskHandle::Tokio(handle));
 
+            let handle = glib::spawn_future_local(clone!(
+                #[weak]
+                imp,
+                async move {
+                    while let Ok(is_visible) = rx.recv().await {
+                        imp.obj().sync_device_visibility_ui(is_visible);
+                    }
+                }
+            ));
+            imp.looping_async_tasks
+                .borrow_mut()
+                .push(LoopingTaskHandle::Glib(handle));
+
             // A task that handles BLE advertisements from other nearby devices
             //
             // Close previous tasks and restart service whenever running RQS::run,
@@ -2489,3 +5955,102 @@ impl PacketApplicationWindow {
         rqs_init_handle
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn static_port_validation() {
+        assert!(is_valid_static_port(1025));
+        assert!(is_valid_static_port(9300));
+        assert!(is_valid_static_port(u16::MAX as i32));
+
+        assert!(!is_valid_static_port(-1));
+        assert!(!is_valid_static_port(0));
+        assert!(!is_valid_static_port(80));
+        assert!(!is_valid_static_port(1024));
+        assert!(!is_valid_static_port(u16::MAX as i32 + 1));
+    }
+
+    /// Stand-in for `rqs_lib::RQS`, which can't be started in tests.
+    #[derive(Debug, Default)]
+    struct FakeService {
+        generation: u32,
+        download_path: Option<PathBuf>,
+    }
+
+    impl ReconfigurableService for FakeService {
+        fn set_download_path(&mut self, path: Option<PathBuf>) {
+            self.download_path = path;
+        }
+    }
+
+    /// Takes the old service out and puts the new one in across an await
+    /// point, like `stop_rqs_service` and `setup_rqs_service` do.
+    async fn restart(service_ops_lock: &Mutex<()>, rqs: &Mutex<Option<FakeService>>) {
+        let old = RefCell::new(None);
+        restart_service_serialized(
+            service_ops_lock,
+            async || {
+                *old.borrow_mut() = rqs.lock().await.take();
+                tokio::task::yield_now().await;
+            },
+            async || {
+                let old = old.borrow_mut().take().unwrap();
+                *rqs.lock().await = Some(FakeService {
+                    generation: old.generation + 1,
+                    download_path: old.download_path,
+                });
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn download_folder_change_waits_out_restart() {
+        let service_ops_lock = Mutex::new(());
+        let rqs = Mutex::new(Some(FakeService::default()));
+
+        // The restart gets polled first, so it's midway through when the
+        // folder change comes in
+        let (_, is_applied) = tokio::join!(
+            restart(&service_ops_lock, &rqs),
+            set_service_download_path(&service_ops_lock, &rqs, PathBuf::from("/tmp/Downloads"),),
+        );
+
+        assert!(is_applied);
+        let rqs = rqs.lock().await;
+        let rqs = rqs.as_ref().unwrap();
+        assert_eq!(rqs.generation, 1);
+        assert_eq!(rqs.download_path, Some(PathBuf::from("/tmp/Downloads")));
+    }
+
+    #[tokio::test]
+    async fn download_folder_change_without_service() {
+        let service_ops_lock = Mutex::new(());
+        let rqs = Mutex::new(None::<FakeService>);
+
+        assert!(
+            !set_service_download_path(&service_ops_lock, &rqs, PathBuf::from("/tmp/Downloads"))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn restarts_run_one_after_another() {
+        let service_ops_lock = Mutex::new(());
+        let rqs = Mutex::new(Some(FakeService::default()));
+
+        // Would panic on the `unwrap()` if the second restart found the
+        // service already taken out by the first
+        tokio::join!(
+            restart(&service_ops_lock, &rqs),
+            restart(&service_ops_lock, &rqs),
+        );
+
+        assert_eq!(rqs.lock().await.as_ref().unwrap().generation, 2);
+    }
+}